@@ -0,0 +1,100 @@
+//! Known answers for each day's committed `input`.
+//!
+//! `dayNN/answers` uses the same `partN: <answer>` lines that the day binaries
+//! print, so it can be regenerated with `cargo run -- input > answers`. Blank
+//! lines and lines starting with `#` are ignored.
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::path::Path;
+
+#[derive(Default, PartialEq, Eq, Debug)]
+pub struct Answers {
+    pub part1: Option<String>,
+    pub part2: Option<String>,
+}
+
+impl Answers {
+    pub fn get(&self, part: u8) -> Option<&str> {
+        match part {
+            1 => self.part1.as_deref(),
+            _ => self.part2.as_deref(),
+        }
+    }
+
+    /// Returns `None` if the file does not exist.
+    pub fn load(path: &Path) -> Result<Option<Answers>> {
+        match std::fs::read_to_string(path) {
+            Ok(s) => Ok(Some(s.parse().with_context(|| format!("parsing {}", path.display()))?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("reading {}", path.display())),
+        }
+    }
+}
+
+impl std::str::FromStr for Answers {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut answers = Answers::default();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| anyhow!("line {}: expected `partN: answer`", i + 1))?;
+            let slot = match key {
+                "part1" => &mut answers.part1,
+                "part2" => &mut answers.part2,
+                _ => bail!("line {}: unknown key {:?}", i + 1, key),
+            };
+            if slot.is_some() {
+                bail!("line {}: duplicate {}", i + 1, key);
+            }
+            *slot = Some(value.trim().to_string());
+        }
+        Ok(answers)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Verdict {
+    Pass,
+    Fail,
+    Missing,
+}
+
+pub fn check(expected: Option<&str>, actual: &str) -> Verdict {
+    match expected {
+        None => Verdict::Missing,
+        Some(e) if e == actual => Verdict::Pass,
+        Some(_) => Verdict::Fail,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let answers: Answers = "# day 99\npart1: 42\n\npart2: abc\n".parse().unwrap();
+        assert_eq!(answers.get(1), Some("42"));
+        assert_eq!(answers.get(2), Some("abc"));
+
+        let partial: Answers = "part2: 7".parse().unwrap();
+        assert_eq!(partial, Answers { part1: None, part2: Some("7".to_string()) });
+
+        assert!("part3: 1".parse::<Answers>().is_err());
+        assert!("part1 1".parse::<Answers>().is_err());
+        assert!("part1: 1\npart1: 2".parse::<Answers>().is_err());
+    }
+
+    #[test]
+    fn verdicts() {
+        assert_eq!(check(Some("42"), "42"), Verdict::Pass);
+        assert_eq!(check(Some("42"), "43"), Verdict::Fail);
+        assert_eq!(check(None, "42"), Verdict::Missing);
+    }
+}
@@ -0,0 +1,223 @@
+//! `aoc bench`: repeated timing of each day's parse, part 1 and part 2 stages.
+//!
+//! Baselines are plain text, one `<day> <stage> <min> <median> <mean>` line per
+//! stage with durations in nanoseconds, so they diff cleanly when committed.
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Stage {
+    Parse,
+    Part1,
+    Part2,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 3] = [Stage::Parse, Stage::Part1, Stage::Part2];
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Stage::Parse => "parse",
+            Stage::Part1 => "part1",
+            Stage::Part2 => "part2",
+        })
+    }
+}
+
+impl std::str::FromStr for Stage {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Stage::ALL
+            .into_iter()
+            .find(|stage| stage.to_string() == s)
+            .ok_or_else(|| anyhow!("unknown stage {:?}", s))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+}
+
+impl Stats {
+    /// # Panics
+    /// Panics if `samples` is empty.
+    pub fn from_samples(mut samples: Vec<Duration>) -> Stats {
+        assert!(!samples.is_empty(), "no samples");
+        samples.sort();
+        let n = samples.len();
+        let median = if n % 2 == 1 {
+            samples[n / 2]
+        } else {
+            (samples[n / 2 - 1] + samples[n / 2]) / 2
+        };
+        Stats {
+            min: samples[0],
+            median,
+            mean: samples.iter().sum::<Duration>() / n as u32,
+        }
+    }
+}
+
+pub struct Options {
+    pub warmup: usize,
+    pub iters: usize,
+    /// Stop iterating a stage once this much time has been spent on it, after
+    /// at least one measured run.
+    pub max_time: Duration,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            warmup: 1,
+            iters: 10,
+            max_time: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Times `f` according to `opts`, returning the stats and the last result.
+pub fn measure<T>(opts: &Options, mut f: impl FnMut() -> Result<T>) -> Result<(Stats, T)> {
+    for _ in 0..opts.warmup {
+        f()?;
+    }
+    let mut samples = vec![];
+    let mut spent = Duration::ZERO;
+    loop {
+        let start = Instant::now();
+        let res = f()?;
+        let elapsed = start.elapsed();
+        samples.push(elapsed);
+        spent += elapsed;
+        if samples.len() >= opts.iters.max(1) || spent >= opts.max_time {
+            return Ok((Stats::from_samples(samples), res));
+        }
+    }
+}
+
+pub type Results = BTreeMap<(u32, Stage), Stats>;
+
+pub fn save(path: &Path, results: &Results) -> Result<()> {
+    let mut out = String::from("# day stage min_ns median_ns mean_ns\n");
+    for ((day, stage), stats) in results {
+        out += &format!(
+            "{} {} {} {} {}\n",
+            day,
+            stage,
+            stats.min.as_nanos(),
+            stats.median.as_nanos(),
+            stats.mean.as_nanos()
+        );
+    }
+    std::fs::write(path, out).with_context(|| format!("writing {}", path.display()))
+}
+
+pub fn parse_baseline(s: &str) -> Result<Results> {
+    let mut results = Results::new();
+    for (i, line) in s.lines().enumerate() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields = line.split_ascii_whitespace().collect::<Vec<_>>();
+        let [day, stage, min, median, mean] = fields[..] else {
+            bail!("line {}: expected 5 fields, got {}", i + 1, fields.len());
+        };
+        let nanos = |s: &str| -> Result<Duration> {
+            Ok(Duration::from_nanos(
+                s.parse().with_context(|| format!("line {}", i + 1))?,
+            ))
+        };
+        results.insert(
+            (day.parse()?, stage.parse()?),
+            Stats {
+                min: nanos(min)?,
+                median: nanos(median)?,
+                mean: nanos(mean)?,
+            },
+        );
+    }
+    Ok(results)
+}
+
+pub fn load(path: &Path) -> Result<Results> {
+    let s = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    parse_baseline(&s).with_context(|| format!("parsing {}", path.display()))
+}
+
+/// Relative change of the median against the baseline, e.g. `0.25` for 25% slower.
+pub fn change(baseline: &Stats, current: &Stats) -> f64 {
+    current.median.as_secs_f64() / baseline.median.as_secs_f64().max(1e-9) - 1.0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn stats() {
+        let s = Stats::from_samples(vec![ms(5), ms(1), ms(3)]);
+        assert_eq!(s, Stats { min: ms(1), median: ms(3), mean: ms(3) });
+        let s = Stats::from_samples(vec![ms(4), ms(1), ms(2), ms(9)]);
+        assert_eq!(s, Stats { min: ms(1), median: ms(3), mean: ms(4) });
+    }
+
+    #[test]
+    fn measure_counts() {
+        let mut calls = 0;
+        let opts = Options { warmup: 2, iters: 5, max_time: Duration::from_secs(60) };
+        let (_, last) = measure(&opts, || {
+            calls += 1;
+            Ok(calls)
+        })
+        .unwrap();
+        assert_eq!((calls, last), (7, 7));
+
+        let mut calls = 0;
+        let opts = Options { warmup: 0, iters: 100, max_time: Duration::ZERO };
+        measure(&opts, || {
+            calls += 1;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(calls, 1);
+
+        assert!(measure(&Options::default(), || Err::<(), _>(anyhow!("boom"))).is_err());
+    }
+
+    #[test]
+    fn baseline_roundtrip() {
+        let mut results = Results::new();
+        results.insert((2, Stage::Parse), Stats { min: ms(1), median: ms(2), mean: ms(3) });
+        results.insert((7, Stage::Part2), Stats { min: ms(10), median: ms(20), mean: ms(30) });
+        let path = std::env::temp_dir().join(format!("aoc-bench-test-{}", std::process::id()));
+        save(&path, &results).unwrap();
+        let loaded = load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, results);
+
+        assert!(parse_baseline("2 parse 1 2").is_err());
+        assert!(parse_baseline("2 part3 1 2 3").is_err());
+    }
+
+    #[test]
+    fn relative_change() {
+        let base = Stats { min: ms(1), median: ms(100), mean: ms(1) };
+        let cur = Stats { min: ms(1), median: ms(125), mean: ms(1) };
+        assert!((change(&base, &cur) - 0.25).abs() < 1e-9);
+        assert!(change(&cur, &base) < 0.0);
+    }
+}
@@ -0,0 +1,44 @@
+use common::{DynSolution, Erased};
+use std::path::PathBuf;
+
+pub struct Day {
+    pub num: u32,
+    pub solution: &'static dyn DynSolution,
+}
+
+/// The repository root, holding one `dayNN` crate per day.
+pub fn repo_root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..")
+}
+
+impl Day {
+    /// The day's crate directory, `dayNN` at the repository root.
+    pub fn dir(&self) -> PathBuf {
+        repo_root().join(format!("day{:02}", self.num))
+    }
+
+    /// The committed puzzle input for this day.
+    pub fn default_input(&self) -> PathBuf {
+        self.dir().join("input")
+    }
+
+    /// The known-correct answers for `default_input`, see [`crate::answers`].
+    pub fn answers_path(&self) -> PathBuf {
+        self.dir().join("answers")
+    }
+}
+
+// `aoc new-day` inserts entries into this table, keep one day per line.
+pub const DAYS: &[Day] = &[
+    Day { num: 2, solution: &Erased::<day02::Day02>::new() },
+    Day { num: 3, solution: &Erased::<day03::Day03>::new() },
+    Day { num: 4, solution: &Erased::<day04::Day04>::new() },
+    Day { num: 5, solution: &Erased::<day05::Day05>::new() },
+    Day { num: 6, solution: &Erased::<day06::Day06>::new() },
+    Day { num: 7, solution: &Erased::<day07::Day07>::new() },
+    Day { num: 8, solution: &Erased::<day08::Day08>::new() },
+];
+
+pub fn find(num: u32) -> Option<&'static Day> {
+    DAYS.iter().find(|d| d.num == num)
+}
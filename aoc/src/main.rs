@@ -0,0 +1,378 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::any::Any;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+mod answers;
+mod bench;
+mod days;
+mod new_day;
+
+use answers::{Answers, Verdict};
+use common::cli::Parts;
+use common::report::{input_hash, Format, Record};
+use days::Day;
+
+const USAGE: &str = "usage (-v, -vv or --quiet may be given anywhere):
+    aoc list
+    aoc run <day> [--part 1|2|both] [--input <path>] [--format text|json]
+    aoc all [--format text|json]
+    aoc verify [<day>...]
+    aoc new-day <day>
+    aoc bench [<day>...] [--warmup <n>] [--iters <n>] [--max-time <secs>]
+              [--save <path>] [--baseline <path>] [--threshold <percent>]";
+
+struct PartResult {
+    answer: Result<String>,
+    elapsed: Duration,
+}
+
+fn timed<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let res = f();
+    (res, start.elapsed())
+}
+
+fn run_part(day: &Day, part: u8, input: &dyn Any) -> PartResult {
+    let (answer, elapsed) = timed(|| match part {
+        1 => day.solution.part1(input),
+        _ => day.solution.part2(input),
+    });
+    PartResult { answer, elapsed }
+}
+
+fn record(day: &Day, part: u8, res: PartResult, parse_time: Duration, input: &str) -> Record {
+    Record {
+        day: day.num,
+        part,
+        answer: res.answer.map_err(|e| format!("{:#}", e)),
+        parse_time,
+        part_time: res.elapsed,
+        input_hash: input_hash(input),
+    }
+}
+
+fn parse_format(args: &mut impl Iterator<Item = String>) -> Result<Format> {
+    args.next()
+        .ok_or_else(|| anyhow!("--format needs a value"))?
+        .parse()
+}
+
+fn read_input(day: &Day, path: Option<PathBuf>) -> Result<String> {
+    let path = path.unwrap_or_else(|| day.default_input());
+    std::fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))
+}
+
+fn cmd_list() {
+    for day in days::DAYS {
+        println!("day {:02}  {}", day.num, day.default_input().display());
+    }
+}
+
+fn cmd_run(mut args: impl Iterator<Item = String>) -> Result<()> {
+    let day = args.next().ok_or_else(|| anyhow!("missing day\n{}", USAGE))?;
+    let num: u32 = day.parse().with_context(|| format!("invalid day {:?}", day))?;
+    let day = days::find(num).ok_or_else(|| anyhow!("no solver for day {}", num))?;
+
+    let mut parts = Parts::Both;
+    let mut input_path = None;
+    let mut format = Format::Text;
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--format" => format = parse_format(&mut args)?,
+            "--part" => {
+                let v = args.next().ok_or_else(|| anyhow!("--part needs a value"))?;
+                parts = v.parse()?;
+            }
+            "--input" => {
+                let v = args.next().ok_or_else(|| anyhow!("--input needs a value"))?;
+                input_path = Some(PathBuf::from(v));
+            }
+            _ => bail!("unexpected argument {:?}\n{}", arg, USAGE),
+        }
+    }
+
+    let input = read_input(day, input_path)?;
+    let (parsed, parse_time) = timed(|| day.solution.parse(&input));
+    let parsed = parsed.with_context(|| format!("day {} parse", num))?;
+    if format == Format::Text {
+        println!("day {:02} parse ({:.2?})", num, parse_time);
+    }
+
+    let mut failed = false;
+    for part in [1, 2].into_iter().filter(|p| parts.includes(*p)) {
+        let res = run_part(day, part, &*parsed);
+        if format == Format::Json {
+            failed |= res.answer.is_err();
+            println!("{}", record(day, part, res, parse_time, &input).to_json());
+            continue;
+        }
+        let answer = res.answer.with_context(|| format!("day {} part {}", num, part))?;
+        println!("day {:02} part {}: {} ({:.2?})", num, part, answer, res.elapsed);
+    }
+    if failed {
+        bail!("some parts failed");
+    }
+    Ok(())
+}
+
+fn format_answer(res: &PartResult) -> String {
+    match &res.answer {
+        Ok(n) => n.clone(),
+        Err(e) => format!("error: {}", e),
+    }
+}
+
+/// Reports a day that failed before either part could run.
+fn report_day_error(day: &Day, format: Format, e: &anyhow::Error, input: &str) {
+    match format {
+        Format::Text => println!("{:>3}  error: {:#}", day.num, e),
+        Format::Json => {
+            for part in [1, 2] {
+                let res = PartResult {
+                    answer: Err(anyhow!("{:#}", e)),
+                    elapsed: Duration::ZERO,
+                };
+                println!("{}", record(day, part, res, Duration::ZERO, input).to_json());
+            }
+        }
+    }
+}
+
+fn cmd_all(mut args: impl Iterator<Item = String>) -> Result<()> {
+    let mut format = Format::Text;
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--format" => format = parse_format(&mut args)?,
+            _ => bail!("unexpected argument {:?}\n{}", arg, USAGE),
+        }
+    }
+
+    let mut failed = false;
+    let mut total = Duration::ZERO;
+    if format == Format::Text {
+        println!("{:>3}  {:>20}  {:>20}  {:>10}", "day", "part 1", "part 2", "time");
+    }
+    for day in days::DAYS {
+        let input = match read_input(day, None) {
+            Ok(input) => input,
+            Err(e) => {
+                failed = true;
+                report_day_error(day, format, &e, "");
+                continue;
+            }
+        };
+        let (parsed, parse_elapsed) = timed(|| day.solution.parse(&input));
+        let parsed = match parsed {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                failed = true;
+                report_day_error(day, format, &e, &input);
+                continue;
+            }
+        };
+        let p1 = run_part(day, 1, &*parsed);
+        let p2 = run_part(day, 2, &*parsed);
+        failed |= p1.answer.is_err() || p2.answer.is_err();
+        if format == Format::Json {
+            println!("{}", record(day, 1, p1, parse_elapsed, &input).to_json());
+            println!("{}", record(day, 2, p2, parse_elapsed, &input).to_json());
+            continue;
+        }
+        let elapsed = parse_elapsed + p1.elapsed + p2.elapsed;
+        total += elapsed;
+        println!(
+            "{:>3}  {:>20}  {:>20}  {:>10.2?}",
+            day.num,
+            format_answer(&p1),
+            format_answer(&p2),
+            elapsed
+        );
+    }
+    if format == Format::Text {
+        println!("{:>3}  {:>20}  {:>20}  {:>10.2?}", "", "", "total", total);
+    }
+    if failed {
+        bail!("some solvers failed");
+    }
+    Ok(())
+}
+
+/// Runs each day on its committed input and compares against `dayNN/answers`.
+fn cmd_verify(args: impl Iterator<Item = String>) -> Result<()> {
+    let selected = args
+        .map(|a| a.parse::<u32>().with_context(|| format!("invalid day {:?}", a)))
+        .collect::<Result<Vec<u32>>>()?;
+    let mut counts = [0usize; 3];
+    let mut errors = 0;
+    for day in days::DAYS {
+        if !selected.is_empty() && !selected.contains(&day.num) {
+            continue;
+        }
+        let answers = Answers::load(&day.answers_path())?.unwrap_or_default();
+        let parsed = read_input(day, None).and_then(|input| day.solution.parse(&input));
+        let parsed = match parsed {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                errors += 1;
+                println!("day {:02}: error: {:#}", day.num, e);
+                continue;
+            }
+        };
+        for part in [1, 2] {
+            let res = run_part(day, part, &*parsed);
+            let actual = match res.answer {
+                Ok(actual) => actual,
+                Err(e) => {
+                    errors += 1;
+                    println!("day {:02} part {}: error: {:#}", day.num, part, e);
+                    continue;
+                }
+            };
+            let expected = answers.get(part);
+            let verdict = answers::check(expected, &actual);
+            counts[verdict as usize] += 1;
+            match verdict {
+                Verdict::Pass => println!("day {:02} part {}: pass", day.num, part),
+                Verdict::Fail => println!(
+                    "day {:02} part {}: FAIL expected {} got {}",
+                    day.num,
+                    part,
+                    expected.unwrap_or_default(),
+                    actual
+                ),
+                Verdict::Missing => {
+                    println!("day {:02} part {}: missing (got {})", day.num, part, actual)
+                }
+            }
+        }
+    }
+    let [passed, failed, missing] = counts;
+    println!(
+        "{} passed, {} failed, {} missing, {} errors",
+        passed, failed, missing, errors
+    );
+    if failed > 0 || errors > 0 {
+        bail!("verification failed");
+    }
+    Ok(())
+}
+
+fn cmd_bench(mut args: impl Iterator<Item = String>) -> Result<()> {
+    let mut opts = bench::Options::default();
+    let mut selected = vec![];
+    let mut save = None;
+    let mut baseline = None;
+    let mut threshold = 10.0;
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| anyhow!("{} needs a value", name))
+        };
+        match &*arg {
+            "--warmup" => opts.warmup = value(&arg)?.parse()?,
+            "--iters" => opts.iters = value(&arg)?.parse()?,
+            "--max-time" => opts.max_time = Duration::from_secs_f64(value(&arg)?.parse()?),
+            "--save" => save = Some(PathBuf::from(value(&arg)?)),
+            "--baseline" => baseline = Some(bench::load(&PathBuf::from(value(&arg)?))?),
+            "--threshold" => threshold = value(&arg)?.parse()?,
+            _ => selected.push(
+                arg.parse::<u32>()
+                    .with_context(|| format!("unexpected argument {:?}\n{}", arg, USAGE))?,
+            ),
+        }
+    }
+
+    let mut results = bench::Results::new();
+    let mut regressions = 0;
+    let mut errors = 0;
+    println!(
+        "{:>3}  {:<5}  {:>10}  {:>10}  {:>10}  {:>10}",
+        "day", "stage", "min", "median", "mean", "vs base"
+    );
+    for day in days::DAYS {
+        if !selected.is_empty() && !selected.contains(&day.num) {
+            continue;
+        }
+        // A missing input counts as a failed parse, so the other days still run.
+        let parsed = read_input(day, None)
+            .and_then(|input| bench::measure(&opts, || day.solution.parse(&input)));
+        let (parse_stats, parsed) = match parsed {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                errors += 1;
+                println!("{:>3}  {:<5}  error: {:#}", day.num, bench::Stage::Parse, e);
+                continue;
+            }
+        };
+        let part1 = bench::measure(&opts, || day.solution.part1(&*parsed));
+        let part2 = bench::measure(&opts, || day.solution.part2(&*parsed));
+        let stages = [
+            (bench::Stage::Parse, Ok(parse_stats)),
+            (bench::Stage::Part1, part1.map(|(s, _)| s)),
+            (bench::Stage::Part2, part2.map(|(s, _)| s)),
+        ];
+        for (stage, stats) in stages {
+            let stats = match stats {
+                Ok(stats) => stats,
+                Err(e) => {
+                    errors += 1;
+                    println!("{:>3}  {:<5}  error: {:#}", day.num, stage, e);
+                    continue;
+                }
+            };
+            let versus = match baseline.as_ref().and_then(|b| b.get(&(day.num, stage))) {
+                Some(base) => {
+                    let change = bench::change(base, &stats) * 100.0;
+                    if change > threshold {
+                        regressions += 1;
+                        format!("{:+.1}% SLOWER", change)
+                    } else {
+                        format!("{:+.1}%", change)
+                    }
+                }
+                None => String::new(),
+            };
+            println!(
+                "{:>3}  {:<5}  {:>10.2?}  {:>10.2?}  {:>10.2?}  {:>10}",
+                day.num, stage, stats.min, stats.median, stats.mean, versus
+            );
+            results.insert((day.num, stage), stats);
+        }
+    }
+
+    if let Some(path) = save {
+        bench::save(&path, &results)?;
+    }
+    if errors > 0 {
+        bail!("{} stages failed", errors);
+    }
+    if regressions > 0 {
+        bail!(
+            "{} stages regressed by more than {}% against the baseline",
+            regressions,
+            threshold
+        );
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let mut args = common::log::init_from_args().into_iter();
+    match args.next().as_deref() {
+        Some("list") => {
+            cmd_list();
+            Ok(())
+        }
+        Some("run") => cmd_run(args),
+        Some("all") => cmd_all(args),
+        Some("verify") => cmd_verify(args),
+        Some("bench") => cmd_bench(args),
+        Some("new-day") => {
+            let day = args.next().ok_or_else(|| anyhow!("missing day\n{}", USAGE))?;
+            let num = day.parse().with_context(|| format!("invalid day {:?}", day))?;
+            new_day::create(&days::repo_root(), num)
+        }
+        _ => bail!("{}", USAGE),
+    }
+}
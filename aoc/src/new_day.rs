@@ -0,0 +1,194 @@
+//! `aoc new-day`: scaffolds a `dayNN` crate from `template/` and registers it
+//! with the runner.
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::path::Path;
+
+const TEMPLATE_VERSION: &str = include_str!("../../template/VERSION");
+
+/// (path inside the new crate, template contents)
+const TEMPLATE_FILES: &[(&str, &str)] = &[
+    ("Cargo.toml", include_str!("../../template/Cargo.toml.tmpl")),
+    ("src/lib.rs", include_str!("../../template/src/lib.rs")),
+    ("src/main.rs", include_str!("../../template/src/main.rs")),
+    // Placeholders to paste the puzzle input and examples into.
+    ("input", ""),
+    ("examples/example1", ""),
+];
+
+fn render(template: &str, num: u32) -> String {
+    template
+        .replace("dayXX", &format!("day{:02}", num))
+        .replace("DayXX", &format!("Day{:02}", num))
+        .replace("DAY_NUMBER", &num.to_string())
+        .replace("TEMPLATE_VERSION", TEMPLATE_VERSION.trim())
+}
+
+/// Inserts `new_line` among the `entries` (line index, day number) so the days
+/// stay sorted, or at `fallback` if there are none.
+fn insert_sorted(
+    text: &str,
+    num: u32,
+    entries: &[(usize, u32)],
+    fallback: usize,
+    new_line: String,
+) -> Result<String> {
+    if entries.iter().any(|(_, n)| *n == num) {
+        bail!("day {} is already registered", num);
+    }
+    let at = entries
+        .iter()
+        .find(|(_, n)| *n > num)
+        .map(|(i, _)| *i)
+        .or_else(|| entries.last().map(|(i, _)| i + 1))
+        .unwrap_or(fallback);
+    let mut lines = text.lines().map(String::from).collect::<Vec<String>>();
+    lines.insert(at, new_line);
+    Ok(lines.join("\n") + "\n")
+}
+
+/// Adds the day to the `DAYS` table in `days.rs`.
+fn register_day(days_rs: &str, num: u32) -> Result<String> {
+    let start = days_rs
+        .lines()
+        .position(|l| l.starts_with("pub const DAYS"))
+        .ok_or_else(|| anyhow!("DAYS table not found"))?;
+    let end = days_rs
+        .lines()
+        .enumerate()
+        .skip(start)
+        .find(|(_, l)| l.trim() == "];")
+        .map(|(i, _)| i)
+        .ok_or_else(|| anyhow!("end of DAYS table not found"))?;
+    let entries = days_rs
+        .lines()
+        .enumerate()
+        .take(end)
+        .skip(start + 1)
+        .map(|(i, l)| {
+            let n = l
+                .trim()
+                .strip_prefix("Day { num: ")
+                .and_then(|rest| rest.split(',').next())
+                .and_then(|n| n.parse().ok())
+                .ok_or_else(|| anyhow!("unrecognised DAYS entry on line {}: {}", i + 1, l))?;
+            Ok((i, n))
+        })
+        .collect::<Result<Vec<(usize, u32)>>>()?;
+    let line = format!(
+        "    Day {{ num: {num}, solution: &Erased::<day{num:02}::Day{num:02}>::new() }},"
+    );
+    insert_sorted(days_rs, num, &entries, end, line)
+}
+
+/// Adds the day crate as a path dependency of the runner.
+fn register_dep(cargo_toml: &str, num: u32) -> Result<String> {
+    let entries = cargo_toml
+        .lines()
+        .enumerate()
+        .filter_map(|(i, l)| {
+            let name = l.split_once('=')?.0.trim();
+            let n = name.strip_prefix("day")?.parse().ok()?;
+            Some((i, n))
+        })
+        .collect::<Vec<(usize, u32)>>();
+    let line = format!("day{num:02} = {{ path = \"../day{num:02}\" }}");
+    insert_sorted(cargo_toml, num, &entries, cargo_toml.lines().count(), line)
+}
+
+pub fn create(root: &Path, num: u32) -> Result<()> {
+    if !(1..=25).contains(&num) {
+        bail!("day must be between 1 and 25, got {}", num);
+    }
+    let dir = root.join(format!("day{:02}", num));
+    if dir.exists() {
+        bail!("{} already exists", dir.display());
+    }
+
+    // Work out the registrations first so a failure leaves nothing behind.
+    let days_path = root.join("aoc/src/days.rs");
+    let cargo_path = root.join("aoc/Cargo.toml");
+    let days_rs = std::fs::read_to_string(&days_path)
+        .with_context(|| format!("reading {}", days_path.display()))?;
+    let cargo_toml = std::fs::read_to_string(&cargo_path)
+        .with_context(|| format!("reading {}", cargo_path.display()))?;
+    let days_rs = register_day(&days_rs, num)?;
+    let cargo_toml = register_dep(&cargo_toml, num)?;
+
+    for (name, template) in TEMPLATE_FILES {
+        let path = dir.join(name);
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(&path, render(template, num))
+            .with_context(|| format!("writing {}", path.display()))?;
+    }
+    std::fs::write(&days_path, days_rs)?;
+    std::fs::write(&cargo_path, cargo_toml)?;
+
+    println!("created {}", dir.display());
+    println!("paste the puzzle input into {}", dir.join("input").display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const DAYS_RS: &str = "use x;
+
+pub const DAYS: &[Day] = &[
+    Day { num: 2, solution: &Erased::<day02::Day02>::new() },
+    Day { num: 8, solution: &Erased::<day08::Day08>::new() },
+];
+";
+
+    #[test]
+    fn days_table() {
+        let out = register_day(DAYS_RS, 5).unwrap();
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[4],
+            "    Day { num: 5, solution: &Erased::<day05::Day05>::new() },"
+        );
+        assert!(lines[3].contains("num: 2,"));
+        assert!(lines[5].contains("num: 8,"));
+
+        let out = register_day(DAYS_RS, 12).unwrap();
+        assert!(out.lines().nth(5).unwrap().contains("day12::Day12"));
+        assert_eq!(out.lines().nth(6), Some("];"));
+
+        assert!(register_day(DAYS_RS, 8).is_err());
+        assert!(register_day("fn main() {}", 8).is_err());
+    }
+
+    #[test]
+    fn cargo_deps() {
+        let toml = "[dependencies]\nanyhow = \"1\"\nday02 = { path = \"../day02\" }\n";
+        let out = register_dep(toml, 1).unwrap();
+        assert_eq!(
+            out,
+            "[dependencies]\nanyhow = \"1\"\nday01 = { path = \"../day01\" }\nday02 = { path = \"../day02\" }\n"
+        );
+        let out = register_dep(toml, 9).unwrap();
+        assert!(out.ends_with("day02 = { path = \"../day02\" }\nday09 = { path = \"../day09\" }\n"));
+        assert!(register_dep(toml, 2).is_err());
+    }
+
+    #[test]
+    fn renders_template() {
+        let (_, lib) = TEMPLATE_FILES[1];
+        let lib = render(lib, 9);
+        assert!(lib.contains("pub struct Day09;"));
+        assert!(lib.contains("const DAY: u32 = 9;"));
+        assert!(!lib.contains("XX"));
+        let (_, toml) = TEMPLATE_FILES[0];
+        assert!(render(toml, 9).contains(&format!("template-version = {}", TEMPLATE_VERSION.trim())));
+    }
+
+    #[test]
+    fn refuses_existing_day() {
+        let root = crate::days::repo_root();
+        let err = create(&root, 2).unwrap_err();
+        assert!(err.to_string().contains("already exists"), "{}", err);
+        assert!(create(&root, 26).is_err());
+    }
+}
@@ -0,0 +1,201 @@
+use crate::report::{input_hash, Format, Record};
+use crate::Solution;
+use anyhow::{anyhow, bail, Context, Result};
+use std::io::{IsTerminal, Read};
+use std::path::PathBuf;
+use std::time::Instant;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Parts {
+    One,
+    Two,
+    Both,
+}
+
+impl Parts {
+    pub fn includes(self, part: u8) -> bool {
+        match self {
+            Parts::One => part == 1,
+            Parts::Two => part == 2,
+            Parts::Both => true,
+        }
+    }
+}
+
+impl std::str::FromStr for Parts {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "1" => Ok(Parts::One),
+            "2" => Ok(Parts::Two),
+            "both" => Ok(Parts::Both),
+            _ => Err(anyhow!("invalid part {:?}, expected 1, 2 or both", s)),
+        }
+    }
+}
+
+/// Arguments accepted by every day binary:
+/// `[--part 1|2|both] [--format text|json] [INPUT]`, plus the verbosity flags
+/// handled by [`crate::log::init_from_args`].
+#[derive(PartialEq, Eq, Debug)]
+pub struct Args {
+    pub parts: Parts,
+    pub format: Format,
+    /// Read from stdin when absent.
+    pub input: Option<PathBuf>,
+}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args> {
+        let mut args = args.into_iter();
+        let mut parsed = Args {
+            parts: Parts::Both,
+            format: Format::Text,
+            input: None,
+        };
+        while let Some(arg) = args.next() {
+            match &*arg {
+                "--part" => {
+                    let v = args.next().ok_or_else(|| anyhow!("--part needs a value"))?;
+                    parsed.parts = v.parse()?;
+                }
+                "--format" => {
+                    let v = args.next().ok_or_else(|| anyhow!("--format needs a value"))?;
+                    parsed.format = v.parse()?;
+                }
+                _ if arg.starts_with("--") => bail!("unknown option {:?}", arg),
+                _ if parsed.input.is_none() => parsed.input = Some(PathBuf::from(arg)),
+                _ => bail!("unexpected argument {:?}", arg),
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// For modes that print their own text output: fails if `--format json`
+    /// was given, rather than ignoring it.
+    pub fn require_text(&self, mode: &str) -> Result<()> {
+        if self.format != Format::Text {
+            bail!("--format json doesn't apply to {}", mode);
+        }
+        Ok(())
+    }
+
+    /// [`Args::require_text`] for modes that don't report the two parts
+    /// either, so also fails if `--part` picked one of them.
+    pub fn require_input_only(&self, mode: &str) -> Result<()> {
+        self.require_text(mode)?;
+        if self.parts != Parts::Both {
+            bail!("--part doesn't apply to {}", mode);
+        }
+        Ok(())
+    }
+
+    pub fn read_input(&self) -> Result<String> {
+        match &self.input {
+            Some(path) => {
+                std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))
+            }
+            None => {
+                if std::io::stdin().is_terminal() {
+                    crate::warn!("reading puzzle input from stdin, pass a path to read a file");
+                }
+                let mut input = String::new();
+                std::io::stdin().lock().read_to_string(&mut input)?;
+                Ok(input)
+            }
+        }
+    }
+}
+
+/// Entry point shared by the day binaries.
+///
+/// Only answers go to stdout; solvers write any diagnostics to stderr through
+/// [`crate::log`].
+pub fn main<S: Solution>() -> Result<()> {
+    main_with_args::<S>(crate::log::init_from_args())
+}
+
+/// [`main`] for binaries that take extra flags of their own: `args` are the
+/// process arguments with those flags (and the verbosity flags) removed.
+pub fn main_with_args<S: Solution>(args: Vec<String>) -> Result<()> {
+    let args = Args::parse(args)
+        .context("usage: [-v|-vv|--quiet] [--part 1|2|both] [--format text|json] [INPUT]")?;
+    let raw = args.read_input()?;
+    let start = Instant::now();
+    let input = S::parse(&raw)?;
+    let parse_time = start.elapsed();
+
+    let mut failed = false;
+    for part in [1, 2].into_iter().filter(|p| args.parts.includes(*p)) {
+        let start = Instant::now();
+        let answer = match part {
+            1 => S::part1(&input).map(|a| a.to_string()),
+            _ => S::part2(&input).map(|a| a.to_string()),
+        };
+        let part_time = start.elapsed();
+        match args.format {
+            Format::Text => println!("part{}: {}", part, answer?),
+            Format::Json => {
+                failed |= answer.is_err();
+                let record = Record {
+                    day: S::DAY,
+                    part,
+                    answer: answer.map_err(|e| format!("{:#}", e)),
+                    parse_time,
+                    part_time,
+                    input_hash: input_hash(&raw),
+                };
+                println!("{}", record.to_json());
+            }
+        }
+    }
+    if failed {
+        bail!("some parts failed");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(s: &str) -> Result<Args> {
+        Args::parse(s.split_ascii_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn parse_args() {
+        assert_eq!(
+            args("").unwrap(),
+            Args {
+                parts: Parts::Both,
+                format: Format::Text,
+                input: None
+            }
+        );
+        assert_eq!(
+            args("--part 1 input").unwrap(),
+            Args {
+                parts: Parts::One,
+                format: Format::Text,
+                input: Some(PathBuf::from("input"))
+            }
+        );
+        assert_eq!(args("examples/1 --part 2").unwrap().parts, Parts::Two);
+        assert_eq!(args("--format json").unwrap().format, Format::Json);
+        assert!(args("--format yaml").is_err());
+        assert!(args("--part 3").is_err());
+        assert!(args("--part").is_err());
+        assert!(args("a b").is_err());
+        assert!(args("--verbose").is_err());    }
+
+    #[test]
+    fn require_modes() {
+        assert!(args("input").unwrap().require_input_only("--explain").is_ok());
+        assert!(args("--part 2").unwrap().require_text("--stream").is_ok());
+        assert!(args("--part 2").unwrap().require_input_only("--explain").is_err());
+        let err = args("--format json").unwrap().require_text("--stream").unwrap_err();
+        assert_eq!(err.to_string(), "--format json doesn't apply to --stream");
+    }
+}
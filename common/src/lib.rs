@@ -0,0 +1,109 @@
+pub mod cli;
+pub mod log;
+pub mod report;
+#[cfg(feature = "testing")]
+pub mod testing;
+
+use std::any::Any;
+use std::fmt::Display;
+use std::marker::PhantomData;
+
+/// One day's puzzle, split into a parse stage and the two parts.
+///
+/// Both parts receive the same parsed input, so any work shared between them
+/// belongs in `parse`.
+pub trait Solution {
+    /// Day of the month, as used in `dayNN`.
+    const DAY: u32;
+
+    type Input;
+    type Answer: Display;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input>;
+    fn part1(input: &Self::Input) -> anyhow::Result<Self::Answer>;
+    fn part2(input: &Self::Input) -> anyhow::Result<Self::Answer>;
+}
+
+/// Type-erased view of a [`Solution`], so days with different input and
+/// answer types can live in one table.
+pub trait DynSolution: Sync {
+    fn parse(&self, input: &str) -> anyhow::Result<Box<dyn Any>>;
+    /// # Panics
+    /// Panics if `input` did not come from this solution's `parse`.
+    fn part1(&self, input: &dyn Any) -> anyhow::Result<String>;
+    /// # Panics
+    /// Panics if `input` did not come from this solution's `parse`.
+    fn part2(&self, input: &dyn Any) -> anyhow::Result<String>;
+}
+
+pub struct Erased<S>(PhantomData<fn() -> S>);
+
+impl<S> Erased<S> {
+    pub const fn new() -> Self {
+        Erased(PhantomData)
+    }
+}
+
+impl<S> Default for Erased<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> DynSolution for Erased<S>
+where
+    S: Solution,
+    S::Input: 'static,
+{
+    fn parse(&self, input: &str) -> anyhow::Result<Box<dyn Any>> {
+        Ok(Box::new(S::parse(input)?))
+    }
+
+    fn part1(&self, input: &dyn Any) -> anyhow::Result<String> {
+        let input = input.downcast_ref::<S::Input>().expect("input type mismatch");
+        Ok(S::part1(input)?.to_string())
+    }
+
+    fn part2(&self, input: &dyn Any) -> anyhow::Result<String> {
+        let input = input.downcast_ref::<S::Input>().expect("input type mismatch");
+        Ok(S::part2(input)?.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Sum;
+
+    impl Solution for Sum {
+        const DAY: u32 = 0;
+
+        type Input = Vec<u32>;
+        type Answer = u32;
+
+        fn parse(input: &str) -> anyhow::Result<Vec<u32>> {
+            Ok(input
+                .split_ascii_whitespace()
+                .map(|s| s.parse())
+                .collect::<Result<_, _>>()?)
+        }
+
+        fn part1(input: &Vec<u32>) -> anyhow::Result<u32> {
+            Ok(input.iter().sum())
+        }
+
+        fn part2(input: &Vec<u32>) -> anyhow::Result<u32> {
+            Ok(input.iter().product())
+        }
+    }
+
+    #[test]
+    fn erased() {
+        let sol: &dyn DynSolution = &Erased::<Sum>::new();
+        let input = sol.parse("2 3 4").unwrap();
+        assert_eq!(sol.part1(&*input).unwrap(), "9");
+        assert_eq!(sol.part2(&*input).unwrap(), "24");
+        assert!(sol.parse("2 x").is_err());
+    }
+}
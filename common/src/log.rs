@@ -0,0 +1,107 @@
+//! Process-wide, level-filtered diagnostics on stderr.
+//!
+//! Solvers call [`warn!`](crate::warn), [`debug!`](crate::debug) and
+//! [`trace!`](crate::trace); the binaries pick the level from `--quiet`, `-v`
+//! and `-vv`. Messages below the level are not formatted at all, so trace
+//! calls in hot loops only cost a relaxed atomic load.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Level {
+    /// Nothing but answers and fatal errors.
+    Quiet = 0,
+    Warn = 1,
+    Debug = 2,
+    Trace = 3,
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Warn as u8);
+
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn enabled(level: Level) -> bool {
+    level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
+
+/// Removes the verbosity flags from `args`, returning the level they select
+/// (if any) and the remaining arguments.
+pub fn take_flags(args: impl IntoIterator<Item = String>) -> (Option<Level>, Vec<String>) {
+    let mut level = None;
+    let mut rest = vec![];
+    for arg in args {
+        match &*arg {
+            "-q" | "--quiet" => level = Some(Level::Quiet),
+            "-v" | "--verbose" => {
+                level = Some(match level {
+                    Some(Level::Debug | Level::Trace) => Level::Trace,
+                    _ => Level::Debug,
+                })
+            }
+            "-vv" => level = Some(Level::Trace),
+            _ => rest.push(arg),
+        }
+    }
+    (level, rest)
+}
+
+/// Applies [`take_flags`] to the process arguments (without the program name).
+pub fn init_from_args() -> Vec<String> {
+    let (level, rest) = take_flags(std::env::args().skip(1));
+    if let Some(level) = level {
+        set_level(level);
+    }
+    rest
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! log_at {
+    ($level:expr, $($arg:tt)*) => {
+        if $crate::log::enabled($level) {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)*) => { $crate::log_at!($crate::log::Level::Warn, $($arg)*) };
+}
+
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)*) => { $crate::log_at!($crate::log::Level::Debug, $($arg)*) };
+}
+
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)*) => { $crate::log_at!($crate::log::Level::Trace, $($arg)*) };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn flags(s: &str) -> (Option<Level>, Vec<String>) {
+        take_flags(s.split_ascii_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn parse_flags() {
+        assert_eq!(flags("run 2"), (None, vec!["run".to_string(), "2".to_string()]));
+        assert_eq!(flags("-v run").0, Some(Level::Debug));
+        assert_eq!(flags("run -vv").0, Some(Level::Trace));
+        assert_eq!(flags("-v -v").0, Some(Level::Trace));
+        assert_eq!(flags("--quiet input").1, vec!["input".to_string()]);
+        assert_eq!(flags("-q").0, Some(Level::Quiet));
+    }
+
+    #[test]
+    fn ordering() {
+        assert!(Level::Quiet < Level::Warn);
+        assert!(Level::Debug < Level::Trace);
+    }
+}
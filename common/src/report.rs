@@ -0,0 +1,116 @@
+//! Output of solver runs, as text or as one JSON object per line.
+
+use anyhow::anyhow;
+use std::fmt::Write;
+use std::time::Duration;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+impl std::str::FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(anyhow!("invalid format {:?}, expected text or json", s)),
+        }
+    }
+}
+
+/// 64-bit FNV-1a, stable across builds and platforms unlike `DefaultHasher`.
+pub fn input_hash(input: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in input.bytes() {
+        hash ^= u64::from(b);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+pub fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if u32::from(c) < 0x20 => write!(out, "\\u{:04x}", u32::from(c)).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// The outcome of running one part of one day.
+pub struct Record {
+    pub day: u32,
+    pub part: u8,
+    /// The answer, or the error message if the part failed.
+    pub answer: Result<String, String>,
+    pub parse_time: Duration,
+    pub part_time: Duration,
+    pub input_hash: String,
+}
+
+impl Record {
+    pub fn to_json(&self) -> String {
+        let answer = match &self.answer {
+            Ok(answer) => format!("\"answer\":{}", json_string(answer)),
+            Err(e) => format!("\"error\":{}", json_string(e)),
+        };
+        format!(
+            "{{\"day\":{},\"part\":{},{},\"parse_ns\":{},\"time_ns\":{},\"input_hash\":{}}}",
+            self.day,
+            self.part,
+            answer,
+            self.parse_time.as_nanos(),
+            self.part_time.as_nanos(),
+            json_string(&self.input_hash)
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn escaping() {
+        assert_eq!(json_string("plain"), r#""plain""#);
+        assert_eq!(json_string("a\"b\\c\nd\u{1}"), r#""a\"b\\c\nd\u0001""#);
+    }
+
+    #[test]
+    fn hash() {
+        // Reference values for FNV-1a 64.
+        assert_eq!(input_hash(""), "cbf29ce484222325");
+        assert_eq!(input_hash("a"), "af63dc4c8601ec8c");
+    }
+
+    #[test]
+    fn record_json() {
+        let mut rec = Record {
+            day: 6,
+            part: 2,
+            answer: Ok("1670".to_string()),
+            parse_time: Duration::from_nanos(10),
+            part_time: Duration::from_micros(2),
+            input_hash: input_hash(""),
+        };
+        assert_eq!(
+            rec.to_json(),
+            r#"{"day":6,"part":2,"answer":"1670","parse_ns":10,"time_ns":2000,"input_hash":"cbf29ce484222325"}"#
+        );
+        rec.answer = Err("no \"start\"".to_string());
+        assert!(rec.to_json().contains(r#""error":"no \"start\"""#));
+    }
+}
@@ -0,0 +1,16 @@
+//! Helpers for tests and benchmarks, behind the `testing` feature.
+
+/// Xorshift generator for randomized tests: deterministic for a given seed,
+/// which must not be zero.
+#[derive(Clone, Debug)]
+pub struct Rng(pub u64);
+
+impl Rng {
+    /// A value in `0..n`.
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % n
+    }
+}
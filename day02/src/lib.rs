@@ -0,0 +1,881 @@
+use anyhow::{anyhow, bail, Context};
+use common::Solution;
+use std::io::BufRead;
+
+/// Parses a space-separated report into `nums`, reusing its allocation.
+fn parse_report_into(line: &str, nums: &mut Vec<usize>) -> Result<(), anyhow::Error> {
+    nums.clear();
+    for level in line.split(' ') {
+        nums.push(
+            level
+                .parse()
+                .with_context(|| format!("invalid level {:?}", level))?,
+        );
+    }
+    Ok(())
+}
+
+fn parse_report(line: &str) -> Result<Vec<usize>, anyhow::Error> {
+    let mut nums = vec![];
+    parse_report_into(line, &mut nums)?;
+    Ok(nums)
+}
+
+/// Which way a report's levels are allowed to move.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Direction {
+    Increasing,
+    Decreasing,
+    Either,
+}
+
+impl std::str::FromStr for Direction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "up" => Ok(Direction::Increasing),
+            "down" => Ok(Direction::Decreasing),
+            "either" => Ok(Direction::Either),
+            _ => Err(anyhow!(
+                "invalid direction {:?}, expected up, down or either",
+                s
+            )),
+        }
+    }
+}
+
+/// The rules a report has to follow to count as safe.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SafetyPolicy {
+    /// Smallest allowed non-zero step between adjacent levels; equal levels
+    /// are controlled by `plateaus` instead.
+    pub min_step: usize,
+    pub max_step: usize,
+    pub direction: Direction,
+    /// Whether adjacent levels may be equal.
+    pub plateaus: bool,
+    /// How many levels may be removed to make a report safe.
+    pub removals: usize,
+}
+
+impl SafetyPolicy {
+    /// The puzzle's rules: strictly monotonic with steps of 1..=3.
+    pub const PART1: SafetyPolicy = SafetyPolicy {
+        min_step: 1,
+        max_step: 3,
+        direction: Direction::Either,
+        plateaus: false,
+        removals: 0,
+    };
+
+    /// The puzzle's rules with the Problem Dampener.
+    pub const PART2: SafetyPolicy = SafetyPolicy {
+        removals: 1,
+        ..SafetyPolicy::PART1
+    };
+
+    /// Whether `a` followed by `b` is an allowed step, going up if `up`.
+    fn step_ok(&self, a: usize, b: usize, up: bool) -> bool {
+        if a == b {
+            return self.plateaus;
+        }
+        let (lo, hi) = if up { (a, b) } else { (b, a) };
+        lo < hi && (self.min_step..=self.max_step).contains(&(hi - lo))
+    }
+
+    /// The directions to try, as the `up` argument of `step_ok`.
+    fn directions(&self) -> &'static [bool] {
+        match self.direction {
+            Direction::Increasing => &[true],
+            Direction::Decreasing => &[false],
+            Direction::Either => &[true, false],
+        }
+    }
+
+    /// Removes the policy flags from `args`, returning the policy they select
+    /// (starting from [`SafetyPolicy::PART1`]) if any were given.
+    ///
+    /// `--min-step N --max-step N --direction up|down|either --plateaus --removals K`
+    pub fn take_flags(
+        args: impl IntoIterator<Item = String>,
+    ) -> anyhow::Result<(Option<SafetyPolicy>, Vec<String>)> {
+        let mut policy = None;
+        let mut rest = vec![];
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let flag = arg.as_str();
+            if !matches!(
+                flag,
+                "--min-step" | "--max-step" | "--direction" | "--plateaus" | "--removals"
+            ) {
+                rest.push(arg);
+                continue;
+            }
+            let p = policy.get_or_insert(SafetyPolicy::PART1);
+            if flag == "--plateaus" {
+                p.plateaus = true;
+                continue;
+            }
+            let v = args
+                .next()
+                .ok_or_else(|| anyhow!("{} needs a value", flag))?;
+            let context = || format!("invalid {} {:?}", flag, v);
+            match flag {
+                "--min-step" => p.min_step = v.parse().with_context(context)?,
+                "--max-step" => p.max_step = v.parse().with_context(context)?,
+                "--direction" => p.direction = v.parse()?,
+                _ => p.removals = v.parse().with_context(context)?,
+            }
+        }
+        if let Some(p) = policy
+            && p.min_step > p.max_step
+        {
+            bail!(
+                "--min-step {} is larger than --max-step {}",
+                p.min_step,
+                p.max_step
+            );
+        }
+        Ok((policy, rest))
+    }
+}
+
+impl Default for SafetyPolicy {
+    fn default() -> Self {
+        SafetyPolicy::PART1
+    }
+}
+
+pub fn is_safe(line: String, policy: &SafetyPolicy) -> Result<bool, anyhow::Error> {
+    Ok(is_safe_within(&parse_report(&line)?, policy))
+}
+
+/// Whether every adjacent pair is a valid step, ignoring the level at `skip`.
+fn steps_ok(nums: &[usize], skip: Option<usize>, policy: &SafetyPolicy, up: bool) -> bool {
+    let mut levels = nums
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| Some(*i) != skip)
+        .map(|(_, n)| *n);
+    let Some(mut prev) = levels.next() else {
+        return true;
+    };
+    for n in levels {
+        if !policy.step_ok(prev, n, up) {
+            return false;
+        }
+        prev = n;
+    }
+    true
+}
+
+/// Returns the level to remove to make the report safe going up (or down):
+/// `Some(None)` if it is already safe, `None` if one removal is not enough.
+///
+/// Any fix has to remove one side of the first bad pair, so only those two
+/// candidates need rechecking, which keeps this linear.
+fn removal_going(nums: &[usize], policy: &SafetyPolicy, up: bool) -> Option<Option<usize>> {
+    let Some(bad) = nums
+        .windows(2)
+        .position(|w| !policy.step_ok(w[0], w[1], up))
+    else {
+        return Some(None);
+    };
+    [bad, bad + 1]
+        .into_iter()
+        .find(|i| steps_ok(nums, Some(*i), policy, up))
+        .map(Some)
+}
+
+/// [`removal_going`] in whichever allowed direction works, preferring one
+/// that needs no removal, along with that direction.
+fn dampened_removal(nums: &[usize], policy: &SafetyPolicy) -> Option<(bool, Option<usize>)> {
+    policy
+        .directions()
+        .iter()
+        .filter_map(|&up| Some((up, removal_going(nums, policy, up)?)))
+        .min_by_key(|(_, removed)| removed.is_some())
+}
+
+/// Safety check allowing at most one level to be removed.
+fn is_safe_dampened(nums: &[usize], policy: &SafetyPolicy) -> bool {
+    match dampened_removal(nums, policy) {
+        Some((_, skip)) => {
+            common::debug!("{:?} safe, removed {:?}", nums, skip);
+            true
+        }
+        None => {
+            common::debug!("{:?} unsafe", nums);
+            false
+        }
+    }
+}
+
+/// Safety check without removing any levels.
+fn is_safe_undampened(nums: &[usize], policy: &SafetyPolicy) -> bool {
+    policy
+        .directions()
+        .iter()
+        .any(|up| steps_ok(nums, None, policy, *up))
+}
+
+/// Segment tree answering "longest run ending at a level in this range".
+struct MaxTree(Vec<usize>);
+
+impl MaxTree {
+    fn new(len: usize) -> MaxTree {
+        MaxTree(vec![0; 2 * len])
+    }
+
+    fn get(&self, i: usize) -> usize {
+        self.0[self.0.len() / 2 + i]
+    }
+
+    fn raise(&mut self, i: usize, v: usize) {
+        let mut i = self.0.len() / 2 + i;
+        self.0[i] = self.0[i].max(v);
+        while i > 1 {
+            i /= 2;
+            self.0[i] = self.0[2 * i].max(self.0[2 * i + 1]);
+        }
+    }
+
+    /// Maximum over the half-open range `l..r`.
+    fn max(&self, l: usize, r: usize) -> usize {
+        let n = self.0.len() / 2;
+        let (mut l, mut r) = (l + n, r + n);
+        let mut best = 0;
+        while l < r {
+            if l % 2 == 1 {
+                best = best.max(self.0[l]);
+                l += 1;
+            }
+            if r % 2 == 1 {
+                r -= 1;
+                best = best.max(self.0[r]);
+            }
+            l /= 2;
+            r /= 2;
+        }
+        best
+    }
+}
+
+/// Length of the longest subsequence of `nums` that is safe going up (or
+/// down) without removals.
+///
+/// The valid predecessors of a level form a range of values, so this keeps
+/// the best length ending at each distinct value in a [`MaxTree`], making it
+/// O(n log n) for any policy.
+fn longest_safe_subsequence(nums: &[usize], policy: &SafetyPolicy, up: bool) -> usize {
+    let mut values = nums.to_vec();
+    values.sort_unstable();
+    values.dedup();
+    let index = |v: usize| values.partition_point(|x| *x < v);
+    let mut tree = MaxTree::new(values.len());
+    let mut longest = 0;
+    // Zero steps are governed by `plateaus` alone.
+    let min_step = policy.min_step.max(1);
+    for &n in nums {
+        // Values v with a step of min_step..=max_step from v to n.
+        let range = if up {
+            n.checked_sub(min_step)
+                .map(|hi| (n.saturating_sub(policy.max_step), hi))
+        } else {
+            n.checked_add(min_step)
+                .map(|lo| (lo, n.saturating_add(policy.max_step)))
+        };
+        let mut prev = range.map_or(0, |(lo, hi)| {
+            tree.max(index(lo), values.partition_point(|x| *x <= hi))
+        });
+        if policy.plateaus {
+            prev = prev.max(tree.get(index(n)));
+        }
+        tree.raise(index(n), prev + 1);
+        longest = longest.max(prev + 1);
+    }
+    longest
+}
+
+/// The minimum number of levels that must be removed to make the report safe,
+/// ignoring the policy's removal budget.
+pub fn min_removals(nums: &[usize], policy: &SafetyPolicy) -> usize {
+    let keep = policy
+        .directions()
+        .iter()
+        .map(|up| longest_safe_subsequence(nums, policy, *up))
+        .max()
+        .unwrap_or(0);
+    nums.len() - keep
+}
+
+/// Whether the report is safe after removing at most `policy.removals` levels.
+pub fn is_safe_within(nums: &[usize], policy: &SafetyPolicy) -> bool {
+    match policy.removals {
+        0 => is_safe_undampened(nums, policy),
+        1 => is_safe_dampened(nums, policy),
+        k => min_removals(nums, policy) <= k,
+    }
+}
+
+/// Number of reports that are safe under `policy`.
+pub fn count_safe(reports: &[Vec<usize>], policy: &SafetyPolicy) -> usize {
+    reports
+        .iter()
+        .filter(|nums| is_safe_within(nums, policy))
+        .count()
+}
+
+/// Why a pair of adjacent levels breaks the policy.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Reason {
+    StepTooLarge(usize),
+    StepTooSmall(usize),
+    ZeroStep,
+    DirectionChange,
+}
+
+impl std::fmt::Display for Reason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reason::StepTooLarge(d) => write!(f, "step too large ({})", d),
+            Reason::StepTooSmall(d) => write!(f, "step too small ({})", d),
+            Reason::ZeroStep => write!(f, "zero step"),
+            Reason::DirectionChange => write!(f, "direction change"),
+        }
+    }
+}
+
+/// The first pair `nums[pair], nums[pair + 1]` that breaks the policy.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Fault {
+    pub pair: usize,
+    pub reason: Reason,
+}
+
+/// The verdict on one report, and where it went wrong.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Diagnosis {
+    pub safe: bool,
+    /// `None` if the report is safe without any removals.
+    pub fault: Option<Fault>,
+    /// The level the dampener removed to make the report safe.
+    pub removed: Option<usize>,
+}
+
+impl SafetyPolicy {
+    fn reason(&self, a: usize, b: usize, up: bool) -> Option<Reason> {
+        if self.step_ok(a, b, up) {
+            None
+        } else if a == b {
+            Some(Reason::ZeroStep)
+        } else if (a < b) != up {
+            Some(Reason::DirectionChange)
+        } else if a.abs_diff(b) > self.max_step {
+            Some(Reason::StepTooLarge(a.abs_diff(b)))
+        } else {
+            Some(Reason::StepTooSmall(a.abs_diff(b)))
+        }
+    }
+}
+
+/// The direction to judge a report by when no removal settles it: reports
+/// that may go either way go the way of their first non-zero step.
+fn likely_direction(nums: &[usize], policy: &SafetyPolicy) -> bool {
+    match policy.direction {
+        Direction::Increasing => true,
+        Direction::Decreasing => false,
+        Direction::Either => nums
+            .windows(2)
+            .find(|w| w[0] != w[1])
+            .is_none_or(|w| w[0] < w[1]),
+    }
+}
+
+/// Finds the first pair that breaks the policy going up (or down).
+fn first_fault(nums: &[usize], policy: &SafetyPolicy, up: bool) -> Option<Fault> {
+    nums.windows(2).enumerate().find_map(|(pair, w)| {
+        let reason = policy.reason(w[0], w[1], up)?;
+        Some(Fault { pair, reason })
+    })
+}
+
+pub fn diagnose(nums: &[usize], policy: &SafetyPolicy) -> Diagnosis {
+    let fault = first_fault(nums, policy, likely_direction(nums, policy));
+    if fault.is_none() || policy.removals == 0 {
+        return Diagnosis { safe: fault.is_none(), fault, removed: None };
+    }
+    match dampened_removal(nums, policy) {
+        // Point at the fault in the direction the removal was made for.
+        Some((up, removed)) => Diagnosis { safe: true, fault: first_fault(nums, policy, up), removed },
+        None => Diagnosis { safe: is_safe_within(nums, policy), fault, removed: None },
+    }
+}
+
+/// Renders the report with the offending pair underlined and the removed
+/// level marked, e.g.
+///
+/// ```text
+/// safe:   1 3 2 4 5
+///           ^^^ direction change
+///           x removed
+/// ```
+pub fn render(nums: &[usize], diagnosis: &Diagnosis) -> String {
+    const WIDTH: usize = 8;
+    let verdict = if diagnosis.safe { "safe:" } else { "unsafe:" };
+    let mut out = format!("{:<WIDTH$}", verdict);
+    // (start column, end column) of each level.
+    let mut spans = vec![];
+    for (i, n) in nums.iter().enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        let start = out.len();
+        out += &n.to_string();
+        spans.push((start, out.len()));
+    }
+    if let Some(Fault { pair, reason }) = diagnosis.fault {
+        let (start, end) = (spans[pair].0, spans[pair + 1].1);
+        out += &format!("\n{}{} {}", " ".repeat(start), "^".repeat(end - start), reason);
+    }
+    if let Some(removed) = diagnosis.removed {
+        let (start, end) = spans[removed];
+        out += &format!("\n{}{} removed", " ".repeat(start), "x".repeat(end - start));
+    }
+    out
+}
+
+/// Counts from [`tally_reports`].
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Tally {
+    /// Runs of reports separated by blank lines.
+    pub blocks: usize,
+    pub safe: usize,
+    pub unsafe_: usize,
+    pub invalid: usize,
+}
+
+/// A report that could not be parsed, with its 1-based line number.
+#[derive(Debug)]
+pub struct LineError {
+    pub line: usize,
+    pub error: anyhow::Error,
+}
+
+impl std::fmt::Display for LineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {:#}", self.line, self.error)
+    }
+}
+
+/// Checks every report in `reader` against `policy` one line at a time,
+/// passing unparsable lines to `on_error` and carrying on past them and past
+/// blank lines. Memory use only depends on the longest line.
+///
+/// Only I/O errors stop the run.
+pub fn tally_reports(
+    mut reader: impl BufRead,
+    policy: &SafetyPolicy,
+    mut on_error: impl FnMut(LineError),
+) -> anyhow::Result<Tally> {
+    let mut tally = Tally::default();
+    let mut line = String::new();
+    let mut nums = vec![];
+    let mut in_block = false;
+    for lineno in 1.. {
+        line.clear();
+        if reader.read_line(&mut line).with_context(|| format!("reading line {}", lineno))? == 0 {
+            break;
+        }
+        let report = line.trim_end_matches(['\n', '\r']);
+        if report.is_empty() {
+            in_block = false;
+            continue;
+        }
+        if !in_block {
+            tally.blocks += 1;
+            in_block = true;
+        }
+        match parse_report_into(report, &mut nums) {
+            Ok(()) if is_safe_within(&nums, policy) => tally.safe += 1,
+            Ok(()) => tally.unsafe_ += 1,
+            Err(error) => {
+                tally.invalid += 1;
+                on_error(LineError { line: lineno, error });
+            }
+        }
+    }
+    Ok(tally)
+}
+
+/// The original quadratic dampener, which retries the whole check with each
+/// level removed in turn. Kept as the oracle for `is_safe_dampened`.
+#[cfg(test)]
+fn is_safe_report_naive(nums: &[usize]) -> bool {
+    //let cmps = nums.array_windows::<[usize; 2]>().map(|(a, b)| a.cmp(b)).collect::<Vec<std::cmp::Ordering>>();
+    for skip_i in 0..=nums.len() {
+        let nums = if skip_i == nums.len() {
+            nums.to_vec()
+        } else {
+            let mut c = nums.to_vec();
+            c.remove(skip_i);
+            c
+        };
+        let cmps = {
+            let mut cmps = Vec::<Option<std::cmp::Ordering>>::new();
+            for i in 0..nums.len() - 1 {
+                let a = nums[i];
+                let b = nums[i + 1];
+                if ((b as isize) - (a as isize)).abs() > 3 {
+                    cmps.push(None);
+                } else {
+                    cmps.push(Some(a.cmp(&b)));
+                }
+            }
+            cmps
+        };
+        let is_safe = cmps.iter().all(|o| *o == Some(std::cmp::Ordering::Greater))
+            || cmps.iter().all(|o| *o == Some(std::cmp::Ordering::Less));
+        if is_safe {
+            return true;
+        }
+    }
+    false
+}
+
+pub struct Day02;
+
+impl Solution for Day02 {
+    const DAY: u32 = 2;
+
+    type Input = Vec<Vec<usize>>;
+    type Answer = usize;
+
+    /// Reads one report per line, stopping at the first empty line.
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        input
+            .lines()
+            .take_while(|line| !line.is_empty())
+            .map(parse_report)
+            .collect()
+    }
+
+    fn part1(input: &Self::Input) -> anyhow::Result<usize> {
+        Ok(count_safe(input, &SafetyPolicy::PART1))
+    }
+
+    fn part2(input: &Self::Input) -> anyhow::Result<usize> {
+        Ok(count_safe(input, &SafetyPolicy::PART2))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use common::testing::Rng;
+
+    #[test]
+    fn non_numbers() {
+        let wrong = is_safe("1 2 3 4 five".to_string(), &SafetyPolicy::PART2);
+        assert!(wrong.is_err());
+    }
+
+    #[test]
+    fn examples() {
+        assert!(is_safe("7 6 4 2 1".to_string(), &SafetyPolicy::PART2).unwrap());
+        assert!(!is_safe("1 2 7 8 9".to_string(), &SafetyPolicy::PART2).unwrap());
+        assert!(!is_safe("9 7 6 2 1".to_string(), &SafetyPolicy::PART2).unwrap());
+        assert!(is_safe("1 3 2 4 5".to_string(), &SafetyPolicy::PART2).unwrap());
+        assert!(is_safe("8 6 4 4 1".to_string(), &SafetyPolicy::PART2).unwrap());
+        assert!(is_safe("1 3 6 7 9".to_string(), &SafetyPolicy::PART2).unwrap());
+    }
+
+    #[test]
+    fn example_parts() {
+        let data = "7 6 4 2 1\n1 2 7 8 9\n9 7 6 2 1\n1 3 2 4 5\n8 6 4 4 1\n1 3 6 7 9\n";
+        let reports = Day02::parse(data).unwrap();
+        assert_eq!(Day02::part1(&reports).unwrap(), 2);
+        assert_eq!(Day02::part2(&reports).unwrap(), 4);
+    }
+
+    /// Random reports that are mostly close to safe, so both outcomes and
+    /// every removal position get exercised.
+    fn random_report(rng: &mut Rng) -> Vec<usize> {
+        let len = 2 + rng.below(9) as usize;
+        let up = rng.below(2) == 0;
+        let mut n = 10 + rng.below(10) as usize;
+        let mut nums = vec![n];
+        for _ in 1..len {
+            let step = match rng.below(10) {
+                0 => rng.below(8) as usize,
+                1 => 0,
+                _ => 1 + rng.below(3) as usize,
+            };
+            let flip = rng.below(12) == 0;
+            n = if up != flip {
+                n + step
+            } else {
+                n.saturating_sub(step)
+            };
+            nums.push(n);
+        }
+        nums
+    }
+
+    #[test]
+    fn dampener_matches_naive() {
+        let mut rng = Rng(0x2024_1202);
+        let mut safe = 0;
+        for _ in 0..20_000 {
+            let nums = random_report(&mut rng);
+            let expected = is_safe_report_naive(&nums);
+            assert_eq!(
+                is_safe_dampened(&nums, &SafetyPolicy::PART2),
+                expected,
+                "{:?}",
+                nums
+            );
+            safe += expected as usize;
+        }
+        // Make sure the generator isn't degenerate.
+        assert!((2_000..18_000).contains(&safe), "{}", safe);
+    }
+
+    #[test]
+    fn dampener_edges() {
+        assert!(is_safe_dampened(&[5], &SafetyPolicy::PART2));
+        assert!(is_safe_dampened(&[5, 5], &SafetyPolicy::PART2));
+        assert!(is_safe_dampened(&[1, 9, 2, 3], &SafetyPolicy::PART2));
+        assert!(is_safe_dampened(&[9, 1, 2, 3], &SafetyPolicy::PART2));
+        assert!(is_safe_dampened(&[1, 2, 3, 9], &SafetyPolicy::PART2));
+        assert!(!is_safe_dampened(&[1, 9, 2, 9, 3], &SafetyPolicy::PART2));
+        assert!(!is_safe_undampened(&[1, 2, 2], &SafetyPolicy::PART1));
+    }
+
+    #[test]
+    fn long_report() {
+        let mut nums = (0..200_000).collect::<Vec<usize>>();
+        assert!(is_safe_dampened(&nums, &SafetyPolicy::PART2));
+        nums[100_000] = 0;
+        assert!(is_safe_dampened(&nums, &SafetyPolicy::PART2));
+        nums[150_000] = 0;
+        assert!(!is_safe_dampened(&nums, &SafetyPolicy::PART2));
+    }
+
+    /// Tries every subset of levels to remove, smallest first.
+    fn min_removals_naive(nums: &[usize], policy: &SafetyPolicy) -> usize {
+        (0..1u32 << nums.len())
+            .filter(|mask| {
+                let kept = (0..nums.len())
+                    .filter(|i| mask & (1 << i) == 0)
+                    .map(|i| nums[i])
+                    .collect::<Vec<usize>>();
+                is_safe_undampened(&kept, policy)
+            })
+            .map(|mask| mask.count_ones() as usize)
+            .min()
+            .unwrap()
+    }
+
+    #[test]
+    fn min_removals_matches_naive() {
+        let mut rng = Rng(0x2024_0212);
+        for _ in 0..5_000 {
+            let nums = random_report(&mut rng);
+            let strict = &SafetyPolicy::PART1;
+            let removals = min_removals(&nums, strict);
+            assert_eq!(removals, min_removals_naive(&nums, strict), "{:?}", nums);
+            assert_eq!(removals == 0, is_safe_undampened(&nums, strict), "{:?}", nums);
+            assert_eq!(removals <= 1, is_safe_dampened(&nums, strict), "{:?}", nums);
+        }
+    }
+
+    #[test]
+    fn k_removals() {
+        let strict = &SafetyPolicy::PART1;
+        assert_eq!(min_removals(&[], strict), 0);
+        assert_eq!(min_removals(&[1, 9, 2, 9, 3], strict), 2);
+        assert_eq!(min_removals(&[4, 4, 4, 4], strict), 3);
+        assert!(!is_safe_within(&[1, 9, 2, 9, 3], &SafetyPolicy::PART2));
+        let two = SafetyPolicy {
+            removals: 2,
+            ..SafetyPolicy::PART1
+        };
+        assert!(is_safe_within(&[1, 9, 2, 9, 3], &two));
+        // Falling runs are found as well as rising ones.
+        assert_eq!(min_removals(&[9, 7, 20, 6, 4, 30, 1], strict), 2);
+    }
+
+    #[test]
+    fn policies_match_naive() {
+        let mut rng = Rng(0x5afe_2024);
+        for _ in 0..3_000 {
+            let min_step = rng.below(3) as usize;
+            let policy = SafetyPolicy {
+                min_step,
+                max_step: min_step + rng.below(5) as usize,
+                direction: [
+                    Direction::Increasing,
+                    Direction::Decreasing,
+                    Direction::Either,
+                ][rng.below(3) as usize],
+                plateaus: rng.below(2) == 0,
+                removals: rng.below(4) as usize,
+            };
+            let nums = random_report(&mut rng);
+            let removals = min_removals_naive(&nums, &policy);
+            assert_eq!(
+                min_removals(&nums, &policy),
+                removals,
+                "{:?} {:?}",
+                policy,
+                nums
+            );
+            assert_eq!(
+                is_safe_within(&nums, &policy),
+                removals <= policy.removals,
+                "{:?} {:?}",
+                policy,
+                nums
+            );
+        }
+    }
+
+    #[test]
+    fn custom_policy() {
+        let loose = SafetyPolicy {
+            max_step: 5,
+            plateaus: true,
+            ..SafetyPolicy::PART1
+        };
+        assert!(is_safe("1 2 7 8 9".to_string(), &loose).unwrap());
+        assert!(is_safe("8 6 4 4 1".to_string(), &loose).unwrap());
+        let up = SafetyPolicy {
+            direction: Direction::Increasing,
+            ..SafetyPolicy::PART1
+        };
+        assert!(!is_safe("7 6 4 2 1".to_string(), &up).unwrap());
+        assert!(is_safe("1 3 6 7 9".to_string(), &up).unwrap());
+    }
+
+    fn flags(s: &str) -> anyhow::Result<(Option<SafetyPolicy>, Vec<String>)> {
+        SafetyPolicy::take_flags(s.split_ascii_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn policy_flags() {
+        assert_eq!(
+            flags("--part 1 input").unwrap(),
+            (None, vec!["--part".into(), "1".into(), "input".into()])
+        );
+        let (policy, rest) =
+            flags("--max-step 5 input --plateaus --direction down --removals 2").unwrap();
+        assert_eq!(
+            policy,
+            Some(SafetyPolicy {
+                min_step: 1,
+                max_step: 5,
+                direction: Direction::Decreasing,
+                plateaus: true,
+                removals: 2,
+            })
+        );
+        assert_eq!(rest, vec!["input".to_string()]);
+        assert!(flags("--removals").is_err());
+        assert!(flags("--min-step x").is_err());
+        assert!(flags("--direction sideways").is_err());
+        assert!(flags("--min-step 4").is_err());
+    }
+
+    fn explain(line: &str, policy: &SafetyPolicy) -> String {
+        render(&parse_report(line).unwrap(), &diagnose(&parse_report(line).unwrap(), policy))
+    }
+
+    #[test]
+    fn diagnoses() {
+        let p2 = &SafetyPolicy::PART2;
+        let d = diagnose(&[1, 2, 7, 8, 9], p2);
+        assert_eq!(
+            d,
+            Diagnosis {
+                safe: false,
+                fault: Some(Fault { pair: 1, reason: Reason::StepTooLarge(5) }),
+                removed: None,
+            }
+        );
+        let d = diagnose(&[8, 6, 4, 4, 1], p2);
+        assert_eq!(d.fault, Some(Fault { pair: 2, reason: Reason::ZeroStep }));
+        assert_eq!((d.safe, d.removed), (true, Some(2)));
+        let d = diagnose(&[1, 3, 2, 4, 5], &SafetyPolicy::PART1);
+        assert_eq!(d.fault, Some(Fault { pair: 1, reason: Reason::DirectionChange }));
+        assert_eq!((d.safe, d.removed), (false, None));
+        // Judged going up, since removing the first level fixes it that way.
+        let d = diagnose(&[3, 2, 3, 4, 5], p2);
+        assert_eq!(d.fault, Some(Fault { pair: 0, reason: Reason::DirectionChange }));
+        assert_eq!((d.safe, d.removed), (true, Some(0)));
+        let d = diagnose(&[7, 6, 4, 2, 1], p2);
+        assert_eq!(d, Diagnosis { safe: true, fault: None, removed: None });
+        let wide = SafetyPolicy { min_step: 2, ..SafetyPolicy::PART1 };
+        assert_eq!(diagnose(&[1, 3, 4], &wide).fault.unwrap().reason, Reason::StepTooSmall(1));
+    }
+
+    #[test]
+    fn diagnosis_agrees_with_is_safe() {
+        let mut rng = Rng(0xd1a6);
+        for _ in 0..2_000 {
+            let nums = random_report(&mut rng);
+            for policy in [SafetyPolicy::PART1, SafetyPolicy::PART2] {
+                let d = diagnose(&nums, &policy);
+                assert_eq!(d.safe, is_safe_within(&nums, &policy), "{:?}", nums);
+                assert_eq!(d.fault.is_none(), is_safe_undampened(&nums, &policy), "{:?}", nums);
+                if let (Some(fault), Some(removed)) = (d.fault, d.removed) {
+                    assert!((fault.pair..=fault.pair + 1).contains(&removed), "{:?}", nums);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rendering() {
+        assert_eq!(
+            explain("1 3 2 4 5", &SafetyPolicy::PART2),
+            "safe:   1 3 2 4 5\n          ^^^ direction change\n          x removed"
+        );
+        assert_eq!(
+            explain("1 2 7 8 9", &SafetyPolicy::PART2),
+            "unsafe: 1 2 7 8 9\n          ^^^ step too large (5)"
+        );
+        assert_eq!(explain("10 11", &SafetyPolicy::PART1), "safe:   10 11");
+    }
+
+    #[test]
+    fn tally_recovers() {
+        let data = "7 6 4 2 1\n1 2 x 8 9\n\n\n9 7 6 2 1\r\n1 3 2 4 5\n\n1  3\n8 6 4 4 1";
+        let mut errors = vec![];
+        let tally = tally_reports(data.as_bytes(), &SafetyPolicy::PART2, |e| {
+            errors.push(e.to_string())
+        })
+        .unwrap();
+        assert_eq!(
+            tally,
+            Tally { blocks: 3, safe: 3, unsafe_: 1, invalid: 2 }
+        );
+        assert_eq!(
+            errors,
+            vec![
+                "line 2: invalid level \"x\": invalid digit found in string",
+                "line 8: invalid level \"\": cannot parse integer from empty string",
+            ]
+        );
+    }
+
+    #[test]
+    fn tally_matches_parts() {
+        let data = "7 6 4 2 1\n1 2 7 8 9\n9 7 6 2 1\n1 3 2 4 5\n8 6 4 4 1\n1 3 6 7 9\n";
+        for (policy, safe) in [(SafetyPolicy::PART1, 2), (SafetyPolicy::PART2, 4)] {
+            let tally = tally_reports(data.as_bytes(), &policy, |e| panic!("{}", e)).unwrap();
+            assert_eq!(tally, Tally { blocks: 1, safe, unsafe_: 6 - safe, invalid: 0 });
+        }
+    }
+}
@@ -1,79 +1,60 @@
-use std::cmp::Ordering;
-use std::io::BufRead;
+use anyhow::Context;
+use common::cli::Args;
+use common::Solution;
+use day02::{Day02, SafetyPolicy};
+use std::io::BufReader;
 
-fn is_safe(line: String) -> Result<bool, anyhow::Error>{
-    let nums = line
-        .split(' ')
-        .map(|n| n.parse())
-        .collect::<Result<Vec<usize>, _>>()?;
-    //let cmps = nums.array_windows::<[usize; 2]>().map(|(a, b)| a.cmp(b)).collect::<Vec<std::cmp::Ordering>>();
-    for skip_i in 0..=nums.len() {
-        let nums = if skip_i == nums.len() {
-            nums.clone()
-        } else {
-            let mut c = nums.clone();
-            c.remove(skip_i);
-            c
-        };
-        let cmps = {
-            let mut cmps = Vec::<Option<std::cmp::Ordering>>::new();
-            for i in 0..nums.len() - 1 {
-                let a = nums[i];
-                let b = nums[i + 1];
-                if ((b as isize) - (a as isize)).abs() > 3 {
-                    cmps.push(None);
-                } else {
-                    cmps.push(Some(a.cmp(&b)));
-                }
+/// Accepts the usual day arguments, plus the [`SafetyPolicy`] flags. With any
+/// of those given, prints the number of reports that are safe under that
+/// policy instead of the two parts. Either of these switches to the part 2
+/// rules unless a policy is given:
+///
+/// - `--explain` prints every report annotated with why it is (un)safe.
+/// - `--stream` reads the input line by line, skipping blank lines and
+///   reporting unparsable ones instead of stopping, then prints the counts.
+///
+/// `--part` and `--format json` only apply to the two parts, and are rejected
+/// in these modes.
+fn main() -> anyhow::Result<()> {
+    let (policy, mut rest) = SafetyPolicy::take_flags(common::log::init_from_args())?;
+    let explain = rest.iter().any(|arg| arg == "--explain");
+    let stream = rest.iter().any(|arg| arg == "--stream");
+    rest.retain(|arg| arg != "--explain" && arg != "--stream");
+    if !explain && !stream && policy.is_none() {
+        return common::cli::main_with_args::<Day02>(rest);
+    }
+    let args = Args::parse(rest)?;
+    let mode = match (stream, explain) {
+        (true, _) => "--stream",
+        (_, true) => "--explain",
+        _ => "the policy flags",
+    };
+    args.require_input_only(mode)?;
+    if stream {
+        let policy = policy.unwrap_or(SafetyPolicy::PART2);
+        let on_error = |e| common::warn!("{}", e);
+        let tally = match &args.input {
+            Some(path) => {
+                let file = std::fs::File::open(path)
+                    .with_context(|| format!("reading {}", path.display()))?;
+                day02::tally_reports(BufReader::new(file), &policy, on_error)?
             }
-            cmps
+            None => day02::tally_reports(std::io::stdin().lock(), &policy, on_error)?,
         };
-        let is_safe = cmps.iter().all(|o| *o == Some(Ordering::Greater))
-            || cmps.iter().all(|o| *o == Some(Ordering::Less));
-        if is_safe {
-            println!("{:?} {:?}, {} {}", nums, cmps, skip_i, is_safe);
-            return Ok(true);
-        }
+        println!("blocks: {}", tally.blocks);
+        println!("safe: {}", tally.safe);
+        println!("unsafe: {}", tally.unsafe_);
+        println!("invalid: {}", tally.invalid);
+        return Ok(());
     }
-    println!("{:?} {}", nums, false);
-    Ok(false)
-}
-
-fn main() -> Result<(), anyhow::Error> {
-    let input = std::io::stdin().lock().lines();
-
-    let mut safe_count = 0;
-    for line in input {
-        let line = line?;
-        if line.is_empty() {
-            break;
-        }
-        if is_safe(line)? {
-            safe_count += 1;
+    let reports = Day02::parse(&args.read_input()?)?;
+    if explain {
+        let policy = policy.unwrap_or(SafetyPolicy::PART2);
+        for nums in &reports {
+            println!("{}", day02::render(nums, &day02::diagnose(nums, &policy)));
         }
-        //let nums = line.split(' ').map(|n| n.parse::<usize>()).collect::<Result<Vec<_>, _>>()?;
+    } else if let Some(policy) = policy {
+        println!("safe: {}", day02::count_safe(&reports, &policy));
     }
-    println!("Hello, world! {}", safe_count);
     Ok(())
 }
-
-#[cfg(test)]
-mod test {
-    use super::is_safe;
-
-    #[test]
-    fn non_numbers() {
-        let wrong = is_safe("1 2 3 4 five".to_string());
-        assert!(wrong.is_err());
-    }
-
-    #[test]
-    fn examples() {
-        assert_eq!(is_safe("7 6 4 2 1".to_string()).unwrap(), true);
-        assert_eq!(is_safe("1 2 7 8 9".to_string()).unwrap(), false);
-        assert_eq!(is_safe("9 7 6 2 1".to_string()).unwrap(), false);
-        assert_eq!(is_safe("1 3 2 4 5".to_string()).unwrap(), true);
-        assert_eq!(is_safe("8 6 4 4 1".to_string()).unwrap(), true);
-        assert_eq!(is_safe("1 3 6 7 9".to_string()).unwrap(), true);
-    }
-}
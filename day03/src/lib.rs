@@ -0,0 +1,844 @@
+use common::Solution;
+use std::io::Read;
+use std::ops::RangeInclusive;
+
+/// What the interpreter tracks while running through memory.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Machine {
+    pub enabled: bool,
+    pub acc: i128,
+}
+
+impl Default for Machine {
+    fn default() -> Self {
+        Machine { enabled: true, acc: 0 }
+    }
+}
+
+/// How far a `don't()` reaches before the machine is enabled again.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EnableScope {
+    /// Until the next `do()`, across lines and files.
+    Global,
+    /// Until the end of the line.
+    Line,
+    /// Until the end of the input file.
+    File,
+}
+
+impl std::str::FromStr for EnableScope {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "global" => Ok(EnableScope::Global),
+            "line" => Ok(EnableScope::Line),
+            "file" => Ok(EnableScope::File),
+            _ => Err(anyhow::anyhow!("invalid scope {:?}, expected global, line or file", s)),
+        }
+    }
+}
+
+/// Most operands an instruction can take.
+pub const MAX_ARITY: usize = 4;
+
+/// One entry of an instruction set: `name(a,b,...)` with `arity` operands of
+/// `digits` decimal digits each.
+pub struct Op {
+    pub name: &'static str,
+    pub arity: usize,
+    pub digits: RangeInclusive<usize>,
+    /// Only run `effect` while the machine is enabled.
+    pub gated: bool,
+    /// Returns `None` if the result doesn't fit.
+    pub effect: fn(&mut Machine, &[i128]) -> Option<()>,
+}
+
+fn mul(m: &mut Machine, args: &[i128]) -> Option<()> {
+    m.acc = m.acc.checked_add(args[0].checked_mul(args[1])?)?;
+    Some(())
+}
+
+pub const MUL: Op = Op {
+    name: "mul",
+    arity: 2,
+    digits: 1..=3,
+    gated: true,
+    effect: mul,
+};
+
+/// `mul` with operands of any length.
+pub const WIDE_MUL: Op = Op {
+    digits: 1..=usize::MAX,
+    ..MUL
+};
+
+pub const DO: Op = Op {
+    name: "do",
+    arity: 0,
+    digits: 1..=3,
+    gated: false,
+    effect: |m, _| {
+        m.enabled = true;
+        Some(())
+    },
+};
+
+pub const DONT: Op = Op {
+    name: "don't",
+    arity: 0,
+    digits: 1..=3,
+    gated: false,
+    effect: |m, _| {
+        m.enabled = false;
+        Some(())
+    },
+};
+
+pub const PART1: &[Op] = &[MUL];
+pub const PART2: &[Op] = &[MUL, DO, DONT];
+pub const WIDE_PART1: &[Op] = &[WIDE_MUL];
+pub const WIDE_PART2: &[Op] = &[WIDE_MUL, DO, DONT];
+
+/// One of the built-in instructions found in the corrupted memory.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Instr {
+    Mul { a: i128, b: i128 },
+    Do,
+    Dont,
+}
+
+/// An instruction as matched, with its operands.
+#[derive(Clone, Copy)]
+pub struct Call {
+    pub op: &'static Op,
+    args: [i128; MAX_ARITY],
+}
+
+impl Call {
+    pub fn args(&self) -> &[i128] {
+        &self.args[..self.op.arity]
+    }
+
+    /// The typed instruction, or `None` for ops that aren't built in.
+    pub fn instr(&self) -> Option<Instr> {
+        match (self.op.name, self.args()) {
+            ("mul", &[a, b]) => Some(Instr::Mul { a, b }),
+            ("do", []) => Some(Instr::Do),
+            ("don't", []) => Some(Instr::Dont),
+            _ => None,
+        }
+    }
+}
+
+impl PartialEq for Call {
+    fn eq(&self, other: &Call) -> bool {
+        self.op.name == other.op.name && self.args() == other.args()
+    }
+}
+
+impl std::fmt::Debug for Call {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{:?}", self.op.name, self.args())
+    }
+}
+
+/// Where an instruction was found: the byte range in the whole input, and the
+/// 1-based line it is on (instructions never span lines).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+}
+
+/// An instruction that ran, where it was, and whether the machine was
+/// enabled: for gated instructions that is whether they took effect, for the
+/// others the state they left behind.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Found {
+    pub call: Call,
+    pub span: Span,
+    pub enabled: bool,
+}
+
+impl Found {
+    pub fn instr(&self) -> Option<Instr> {
+        self.call.instr()
+    }
+}
+
+/// An instruction whose operands or result don't fit in an `i128`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Overflow {
+    pub name: &'static str,
+    pub span: Span,
+}
+
+impl std::fmt::Display for Overflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "overflow in {} on line {} at bytes {}..{}",
+            self.name, self.span.line, self.span.start, self.span.end
+        )
+    }
+}
+
+impl std::error::Error for Overflow {}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Progress {
+    /// This many bytes of `name(` have matched.
+    Name(usize),
+    /// `value` is `None` once it has overflowed.
+    Arg { value: Option<i128>, digits: usize },
+    /// Waiting for the `)` of an instruction without operands.
+    Close,
+    /// Matched, ending before this offset.
+    Done(usize),
+}
+
+/// A possible match that started at `span.start`.
+#[derive(Clone, Copy)]
+struct Candidate {
+    op: &'static Op,
+    start: usize,
+    line: usize,
+    progress: Progress,
+    args: [i128; MAX_ARITY],
+    nargs: usize,
+    overflowed: bool,
+}
+
+impl Candidate {
+    /// Advances by one byte, returning false if the match failed.
+    fn step(&mut self, c: u8, pos: usize) -> bool {
+        let op = self.op;
+        self.progress = match self.progress {
+            Progress::Name(n) => {
+                let expected = op.name.as_bytes().get(n).copied().unwrap_or(b'(');
+                if c != expected {
+                    return false;
+                }
+                match (n == op.name.len(), op.arity) {
+                    (false, _) => Progress::Name(n + 1),
+                    (true, 0) => Progress::Close,
+                    (true, _) => Progress::Arg { value: Some(0), digits: 0 },
+                }
+            }
+            Progress::Arg { value, digits } => match c {
+                b'0'..=b'9' if digits < *op.digits.end() => Progress::Arg {
+                    value: value.and_then(|v| v.checked_mul(10)?.checked_add(i128::from(c - b'0'))),
+                    digits: digits + 1,
+                },
+                b',' | b')' if op.digits.contains(&digits) => {
+                    self.args[self.nargs] = value.unwrap_or(0);
+                    self.overflowed |= value.is_none();
+                    self.nargs += 1;
+                    match (c, self.nargs == op.arity) {
+                        (b',', false) => Progress::Arg { value: Some(0), digits: 0 },
+                        (b')', true) => Progress::Done(pos + 1),
+                        _ => return false,
+                    }
+                }
+                _ => return false,
+            },
+            Progress::Close if c == b')' => Progress::Done(pos + 1),
+            Progress::Close => return false,
+            Progress::Done(_) => return true,
+        };
+        true
+    }
+}
+
+/// Runs an instruction set over memory fed in arbitrary chunks.
+///
+/// Every possible match is followed at once, and a finished one only runs
+/// once every match that started earlier has failed, giving the same
+/// leftmost, non-overlapping matches as a regex alternation of the
+/// instructions in table order. Names can be anything, as long as they don't
+/// contain digits, `,`, `(` or `)`.
+pub struct Interpreter {
+    ops: &'static [Op],
+    scope: EnableScope,
+    machine: Machine,
+    /// Ordered by start, then by position in `ops`.
+    candidates: Vec<Candidate>,
+    /// Offset and line of the next byte.
+    pos: usize,
+    line: usize,
+}
+
+impl Interpreter {
+    /// # Panics
+    /// Panics if an instruction takes more than [`MAX_ARITY`] operands.
+    pub fn new(ops: &'static [Op], scope: EnableScope) -> Interpreter {
+        assert!(ops.iter().all(|op| op.arity <= MAX_ARITY), "too many operands");
+        Interpreter {
+            ops,
+            scope,
+            machine: Machine::default(),
+            candidates: vec![],
+            pos: 0,
+            line: 1,
+        }
+    }
+
+    /// Stops at the first instruction that overflows; the interpreter
+    /// shouldn't be used after that.
+    pub fn feed(&mut self, chunk: &[u8], mut emit: impl FnMut(Found)) -> Result<(), Overflow> {
+        for &c in chunk {
+            let pos = self.pos;
+            self.candidates.retain_mut(|cand| cand.step(c, pos));
+            for op in self.ops.iter().filter(|op| op.name.as_bytes().first() == Some(&c)) {
+                self.candidates.push(Candidate {
+                    op,
+                    start: pos,
+                    line: self.line,
+                    progress: Progress::Name(1),
+                    args: [0; MAX_ARITY],
+                    nargs: 0,
+                    overflowed: false,
+                });
+            }
+            self.run_finished(&mut emit)?;
+            self.pos += 1;
+            if c == b'\n' {
+                self.line += 1;
+                if self.scope == EnableScope::Line {
+                    self.machine.enabled = true;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the finished matches that no earlier match can still beat.
+    fn run_finished(&mut self, emit: &mut impl FnMut(Found)) -> Result<(), Overflow> {
+        while let Some(first) = self.candidates.first().copied() {
+            let Progress::Done(end) = first.progress else {
+                return Ok(());
+            };
+            self.candidates.retain(|cand| cand.start >= end);
+            let call = Call { op: first.op, args: first.args };
+            let span = Span { start: first.start, end, line: first.line };
+            let overflow = Overflow { name: first.op.name, span };
+            let before = self.machine.enabled;
+            if before || !first.op.gated {
+                if first.overflowed {
+                    return Err(overflow);
+                }
+                (first.op.effect)(&mut self.machine, call.args()).ok_or(overflow)?;
+            }
+            emit(Found {
+                call,
+                span,
+                enabled: if first.op.gated { before } else { self.machine.enabled },
+            });
+        }
+        Ok(())
+    }
+
+    /// Ends the current input file, running any matches that were waiting on
+    /// earlier ones that can now never finish. Spans start again from the
+    /// beginning of the next file.
+    pub fn end_file(&mut self, mut emit: impl FnMut(Found)) -> Result<(), Overflow> {
+        while !self.candidates.is_empty() {
+            self.candidates.retain(|cand| matches!(cand.progress, Progress::Done(_)));
+            self.run_finished(&mut emit)?;
+        }
+        self.pos = 0;
+        self.line = 1;
+        if self.scope != EnableScope::Global {
+            self.machine.enabled = true;
+        }
+        Ok(())
+    }
+
+    pub fn machine(&self) -> &Machine {
+        &self.machine
+    }
+
+    /// Ends the input, see [`Interpreter::end_file`].
+    pub fn finish(mut self, emit: impl FnMut(Found)) -> Result<Machine, Overflow> {
+        self.end_file(emit)?;
+        Ok(self.machine)
+    }
+}
+
+/// Read size for [`run`]; instructions may straddle reads.
+const CHUNK: usize = 64 * 1024;
+
+/// Runs each interpreter over one input file, reading it once in fixed-size
+/// chunks. `emit` gets the index of the interpreter with each instruction.
+pub fn run_file(
+    mut reader: impl Read,
+    interpreters: &mut [Interpreter],
+    mut emit: impl FnMut(usize, Found),
+) -> anyhow::Result<()> {
+    let mut buf = vec![0; CHUNK];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                for (i, interp) in interpreters.iter_mut().enumerate() {
+                    interp.feed(&buf[..n], |found| emit(i, found))?;
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    for (i, interp) in interpreters.iter_mut().enumerate() {
+        interp.end_file(|found| emit(i, found))?;
+    }
+    Ok(())
+}
+
+/// [`run_file`] for a single input, returning the final machines.
+pub fn run(
+    reader: impl Read,
+    mut interpreters: Vec<Interpreter>,
+    emit: impl FnMut(usize, Found),
+) -> anyhow::Result<Vec<Machine>> {
+    run_file(reader, &mut interpreters, emit)?;
+    Ok(interpreters.iter().map(|interp| *interp.machine()).collect())
+}
+
+/// The answers to both parts.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Sums {
+    pub part1: i128,
+    pub part2: i128,
+}
+
+impl Sums {
+    fn of(interpreters: &[Interpreter; 2]) -> Sums {
+        Sums {
+            part1: interpreters[0].machine().acc,
+            part2: interpreters[1].machine().acc,
+        }
+    }
+}
+
+/// Runs both parts over several input files in turn.
+pub struct Accumulator {
+    interpreters: [Interpreter; 2],
+}
+
+impl Accumulator {
+    /// With operands of any length if `wide`.
+    pub fn new(wide: bool, scope: EnableScope) -> Accumulator {
+        let (part1, part2) = if wide { (WIDE_PART1, WIDE_PART2) } else { (PART1, PART2) };
+        Accumulator {
+            interpreters: [Interpreter::new(part1, scope), Interpreter::new(part2, scope)],
+        }
+    }
+
+    /// Runs the next file, returning its share of the totals.
+    pub fn add(&mut self, reader: impl Read) -> anyhow::Result<Sums> {
+        let before = self.total();
+        run_file(reader, &mut self.interpreters, |_, _| {})?;
+        let after = self.total();
+        Ok(Sums {
+            part1: after.part1 - before.part1,
+            part2: after.part2 - before.part2,
+        })
+    }
+
+    pub fn total(&self) -> Sums {
+        Sums::of(&self.interpreters)
+    }
+}
+
+/// Runs both parts, with operands of any length if `wide`.
+pub fn sum_muls(reader: impl Read, wide: bool) -> anyhow::Result<Sums> {
+    let mut acc = Accumulator::new(wide, EnableScope::Global);
+    acc.add(reader)
+}
+
+/// Every instruction of `ops` in `input`, in order.
+pub fn instructions(
+    input: &str,
+    ops: &'static [Op],
+    scope: EnableScope,
+) -> anyhow::Result<Vec<Found>> {
+    let mut found = vec![];
+    run(input.as_bytes(), vec![Interpreter::new(ops, scope)], |_, f| found.push(f))?;
+    Ok(found)
+}
+
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[1;33m";
+const RESET: &str = "\x1b[0m";
+
+/// `input` with the gated instructions that ran in green, the ones that were
+/// disabled in red, and the rest (such as `do()` and `don't()`) in yellow.
+pub fn explain(input: &str, found: &[Found]) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut at = 0;
+    for f in found {
+        let colour = match (f.call.op.gated, f.enabled) {
+            (true, true) => GREEN,
+            (true, false) => RED,
+            (false, _) => YELLOW,
+        };
+        out += &input[at..f.span.start];
+        out += colour;
+        out += &input[f.span.start..f.span.end];
+        out += RESET;
+        at = f.span.end;
+    }
+    out += &input[at..];
+    out
+}
+
+/// The original regex matcher, kept as the oracle for [`Interpreter`].
+#[cfg(test)]
+fn find_muls(input: &[String], part2: bool) -> anyhow::Result<Vec<(usize, usize)>> {
+    // Compiled once, the differential tests call this thousands of times.
+    static RE: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| {
+        regex::Regex::new(r"mul\(([0-9]{1,3}),([0-9]{1,3})\)|do\(\)|don't\(\)")
+            .expect("regex compile fail")
+    });
+    let re = &*RE;
+    let mut enabled = true;
+    let mut result = vec![];
+
+    for line in input {
+    for c in re.captures_iter(line) {
+        let m = c.get(0).unwrap().as_str(); // Unwrap: Guaranteed to be present for index 0
+        if m == "do()" {
+            enabled = true;
+            continue;
+        } else if m == "don't()" {
+            enabled = false;
+            continue;
+        }
+        let Some(b) = c.get(2) else {
+            panic!("regex matches missing after filtering commands: {}", m);
+        };
+        let b = b.as_str();
+        let a = c.get(1).unwrap().as_str();
+        if enabled || !part2 {
+            result.push((a.parse()?, b.parse()?))
+        }
+    }
+    }
+    Ok(result)
+}
+
+pub struct Day03;
+
+impl Solution for Day03 {
+    const DAY: u32 = 3;
+
+    type Input = String;
+    type Answer = i128;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        Ok(input.to_string())
+    }
+
+    fn part1(input: &Self::Input) -> anyhow::Result<i128> {
+        Ok(sum_muls(input.as_bytes(), false)?.part1)
+    }
+
+    fn part2(input: &Self::Input) -> anyhow::Result<i128> {
+        Ok(sum_muls(input.as_bytes(), false)?.part2)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use common::testing::Rng;
+
+    #[test]
+    fn example() {
+        assert_eq!(
+            find_muls(
+                &[r"xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))".to_string()],
+                false
+            )
+            .unwrap(),
+            vec![(2, 4), (5, 5), (11, 8), (8, 5)]
+        );
+    }
+
+    #[test]
+    fn example2() {
+        assert_eq!(
+            find_muls(
+                &[r"xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))".to_string()],
+                true
+            )
+            .unwrap(),
+            vec![(2, 4), (8, 5)]
+        );
+    }
+
+    #[test]
+    fn example_parts() {
+        let input = Day03::parse(r"xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))").unwrap();
+        assert_eq!(Day03::part1(&input).unwrap(), 161);
+        let input = Day03::parse(r"xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))").unwrap();
+        assert_eq!(Day03::part2(&input).unwrap(), 48);
+    }
+
+    /// Runs `ops` over `input` fed in pieces of `chunk` bytes and keeps the
+    /// operands of the gated instructions that ran, for comparison with
+    /// `find_muls`.
+    fn scan_muls(input: &str, chunk: usize, ops: &'static [Op]) -> Vec<(usize, usize)> {
+        let mut interp = Interpreter::new(ops, EnableScope::Global);
+        let mut result = vec![];
+        let mut keep = |f: Found| {
+            if let (Some(Instr::Mul { a, b }), true) = (f.instr(), f.enabled) {
+                result.push((a as usize, b as usize));
+            }
+        };
+        for piece in input.as_bytes().chunks(chunk) {
+            interp.feed(piece, &mut keep).unwrap();
+        }
+        interp.finish(&mut keep).unwrap();
+        result
+    }
+
+    #[test]
+    fn scanner_examples() {
+        let ex1 = r"xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))";
+        let ex2 = r"xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        for chunk in 1..8 {
+            assert_eq!(scan_muls(ex1, chunk, PART1), vec![(2, 4), (5, 5), (11, 8), (8, 5)]);
+            assert_eq!(scan_muls(ex2, chunk, PART2), vec![(2, 4), (8, 5)]);
+        }
+        assert_eq!(scan_muls("mmul(1,2)mul(1234,5)mul(3,4mul(5,6)", 3, PART1), vec![(1, 2), (5, 6)]);
+        assert_eq!(scan_muls("mul(1,\n2)mul(7,\n", 3, PART1), vec![]);
+        assert_eq!(scan_muls("dodon't()mul(1,1)dodo()mul(2,2)", 2, PART2), vec![(2, 2)]);
+    }
+
+    #[test]
+    fn scanner_matches_regex() {
+        const PIECES: &[&str] = &[
+            "mul(", "mu", "m", "l(", "(", ")", ",", "1", "23", "456", "7890", "do()", "do",
+            "don't()", "don'", "t()", "\n", "x", "mul(12,3)", "[",
+        ];
+        let mut rng = Rng(0x2024_1203);
+        for _ in 0..3_000 {
+            let input = (0..rng.below(40))
+                .map(|_| PIECES[rng.below(PIECES.len() as u64) as usize])
+                .collect::<String>();
+            let lines = input.lines().map(String::from).collect::<Vec<String>>();
+            let chunk = 1 + rng.below(16) as usize;
+            for part2 in [false, true] {
+                assert_eq!(
+                    scan_muls(&input, chunk, if part2 { PART2 } else { PART1 }),
+                    find_muls(&lines, part2).unwrap(),
+                    "{:?}",
+                    input
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn chunked_reader() {
+        // Longer than one chunk, with an instruction straddling the boundary.
+        let mut input = "x".repeat(CHUNK - 4);
+        input += "mul(2,3)don't()mul(4,5)";
+        let sums = sum_muls(input.as_bytes(), false).unwrap();
+        assert_eq!(sums, Sums { part1: 26, part2: 6 });
+    }
+
+    #[test]
+    fn spans() {
+        let input = "xdon't()mul(2,4)\n%do()mul(11,8)";
+        let found = instructions(input, PART2, EnableScope::Global).unwrap();
+        let span = |start, end, line| Span { start, end, line };
+        let call = |op, a, b| Call { op, args: [a, b, 0, 0] };
+        assert_eq!(
+            found,
+            vec![
+                Found { call: call(&DONT, 0, 0), span: span(1, 8, 1), enabled: false },
+                Found { call: call(&MUL, 2, 4), span: span(8, 16, 1), enabled: false },
+                Found { call: call(&DO, 0, 0), span: span(18, 22, 2), enabled: true },
+                Found { call: call(&MUL, 11, 8), span: span(22, 31, 2), enabled: true },
+            ]
+        );
+        assert_eq!(
+            found.iter().map(Found::instr).collect::<Vec<_>>(),
+            vec![
+                Some(Instr::Dont),
+                Some(Instr::Mul { a: 2, b: 4 }),
+                Some(Instr::Do),
+                Some(Instr::Mul { a: 11, b: 8 }),
+            ]
+        );
+        for f in &found {
+            assert!(input[f.span.start..f.span.end].ends_with(')'));
+        }
+        // A failed partial match right before a real one doesn't shift the span.
+        assert_eq!(instructions("mumul(1,1)", PART1, EnableScope::Global).unwrap()[0].span, span(2, 10, 1));
+    }
+
+    #[test]
+    fn explain_colours() {
+        let input = "don't()mul(1,2)x";
+        assert_eq!(
+            explain(input, &instructions(input, PART2, EnableScope::Global).unwrap()),
+            "\x1b[1;33mdon't()\x1b[0m\x1b[31mmul(1,2)\x1b[0mx"
+        );
+    }
+
+    const ADD: Op = Op {
+        name: "add",
+        arity: 2,
+        digits: 1..=2,
+        gated: true,
+        effect: |m, args| {
+            m.acc += args[0] + args[1];
+            Some(())
+        },
+    };
+
+    const NEG: Op = Op {
+        name: "neg",
+        arity: 1,
+        digits: 1..=3,
+        gated: true,
+        effect: |m, args| {
+            m.acc -= args[0];
+            Some(())
+        },
+    };
+
+    /// Flips the enable state, as long as the operand is odd.
+    const FLIP: Op = Op {
+        name: "flip",
+        arity: 1,
+        digits: 1..=1,
+        gated: false,
+        effect: |m, args| {
+            m.enabled ^= args[0] % 2 == 1;
+            Some(())
+        },
+    };
+
+    const EXTENDED: &[Op] = &[MUL, ADD, NEG, DO, DONT, FLIP];
+
+    fn total(input: &str, ops: &'static [Op]) -> i128 {
+        let machines = run(input.as_bytes(), vec![Interpreter::new(ops, EnableScope::Global)], |_, _| {}).unwrap();
+        machines[0].acc
+    }
+
+    #[test]
+    fn extended_instructions() {
+        assert_eq!(total("mul(2,3)add(1,10)neg(100)", EXTENDED), 6 + 11 - 100);
+        assert_eq!(total("add(100,1)add(1,2", EXTENDED), 0);
+        assert_eq!(total("flip(2)mul(1,1)flip(3)mul(2,2)flip(1)mul(3,3)", EXTENDED), 10);
+        // `do()` overlapping the end of a failed `add(`.
+        assert_eq!(total("don't()addo()mul(1,1)", EXTENDED), 1);
+        assert_eq!(total("don't()addo()mul(1,1)", PART2), 1);
+        let found = instructions("add(1,2)mul(3,4)", EXTENDED, EnableScope::Global).unwrap();
+        assert_eq!(found[0].instr(), None);
+        assert_eq!(found[1].instr(), Some(Instr::Mul { a: 3, b: 4 }));
+    }
+
+    /// Builds the regex equivalent to `ops`.
+    fn ops_regex(ops: &[Op]) -> regex::Regex {
+        let alternatives = ops
+            .iter()
+            .map(|op| {
+                let arg = format!("([0-9]{{{},{}}})", op.digits.start(), op.digits.end());
+                let args = vec![arg; op.arity].join(",");
+                format!(r"{}\({}\)", regex::escape(op.name), args)
+            })
+            .collect::<Vec<String>>();
+        regex::Regex::new(&alternatives.join("|")).unwrap()
+    }
+
+    #[test]
+    fn interpreter_matches_regex() {
+        const PIECES: &[&str] = &[
+            "mul(", "add(", "ad", "neg(", "flip(", "fl", "(", ")", ",", "1", "23", "456", "do()",
+            "do", "don't()", "don'", "\n", "x",
+        ];
+        let re = ops_regex(EXTENDED);
+        let mut rng = Rng(0x0b5e55ed);
+        for _ in 0..3_000 {
+            let input = (0..rng.below(40))
+                .map(|_| PIECES[rng.below(PIECES.len() as u64) as usize])
+                .collect::<String>();
+            let expected = re
+                .find_iter(&input)
+                .map(|m| (m.start(), m.end()))
+                .collect::<Vec<_>>();
+            let mut interp = Interpreter::new(EXTENDED, EnableScope::Global);
+            let mut spans = vec![];
+            for piece in input.as_bytes().chunks(1 + rng.below(8) as usize) {
+                interp.feed(piece, |f| spans.push((f.span.start, f.span.end))).unwrap();
+            }
+            interp.finish(|f| spans.push((f.span.start, f.span.end))).unwrap();
+            assert_eq!(spans, expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn wide_operands() {
+        let input = "mul(1234,1000)mul(2,3)don't()mul(99999999999999999999,1)";
+        assert_eq!(sum_muls(input.as_bytes(), false).unwrap(), Sums { part1: 6, part2: 6 });
+        assert_eq!(
+            sum_muls(input.as_bytes(), true).unwrap(),
+            Sums { part1: 1_234_006 + 99999999999999999999, part2: 1_234_006 }
+        );
+    }
+
+    #[test]
+    fn overflow_errors() {
+        let big = "9".repeat(20);
+        let input = format!("mul(1,1)\nxmul({big},{big})");
+        let err = sum_muls(input.as_bytes(), true).unwrap_err();
+        assert_eq!(err.to_string(), "overflow in mul on line 2 at bytes 10..56");
+        // Operands that don't fit at all.
+        let input = format!("mul({},1)", "9".repeat(40));
+        let err = sum_muls(input.as_bytes(), true).unwrap_err();
+        assert_eq!(err.downcast::<Overflow>().unwrap().span.start, 0);
+        // Disabled instructions never run, so can't overflow.
+        let input = format!("don't()mul({},1)", "9".repeat(40));
+        assert_eq!(total(&input, WIDE_PART2), 0);
+        // The total overflowing is caught too.
+        let input = format!("mul({big},{big0})", big0 = "1".repeat(18)).repeat(100);
+        assert!(sum_muls(input.as_bytes(), true).is_err());
+    }
+
+    #[test]
+    fn enable_scopes() {
+        let files = ["mul(1,1)don't()mul(2,2)\nmul(3,3)", "mul(4,4)do()mul(5,5)"];
+        let sums = |scope| {
+            let mut acc = Accumulator::new(false, scope);
+            let per_file = files
+                .iter()
+                .map(|f| acc.add(f.as_bytes()).unwrap().part2)
+                .collect::<Vec<i128>>();
+            (per_file, acc.total())
+        };
+        assert_eq!(sums(EnableScope::Global), (vec![1, 25], Sums { part1: 55, part2: 26 }));
+        assert_eq!(sums(EnableScope::Line), (vec![10, 41], Sums { part1: 55, part2: 51 }));
+        assert_eq!(sums(EnableScope::File), (vec![1, 41], Sums { part1: 55, part2: 42 }));
+        assert!("page".parse::<EnableScope>().is_err());
+    }
+
+    #[test]
+    fn spans_restart_per_file() {
+        let mut interp = Interpreter::new(PART2, EnableScope::Global);
+        let mut spans = vec![];
+        interp.feed(b"x\nmul(1,", |f| spans.push(f.span)).unwrap();
+        interp.end_file(|f| spans.push(f.span)).unwrap();
+        interp.feed(b"2)mul(3,4)", |f| spans.push(f.span)).unwrap();
+        // The `mul` split across files doesn't count.
+        assert_eq!(spans, vec![Span { start: 2, end: 10, line: 1 }]);
+        assert_eq!(interp.finish(|_| {}).unwrap().acc, 12);
+    }
+}
@@ -1,80 +1,88 @@
-use regex::Regex;
-use std::io::BufRead;
+use anyhow::{anyhow, bail, Context};
+use common::cli::Args;
+use day03::{Accumulator, EnableScope, Sums};
 
-const DO: &'static str = "do()";
-const DONT: &'static str = "don't()";
-
-fn find_muls(input: &[String], part2: bool) -> anyhow::Result<Vec<(usize, usize)>> {
-    let re = Regex::new(r"mul\(([0-9]{1,3}),([0-9]{1,3})\)|do\(\)|don't\(\)")
-        .expect("regex compile fail");
-    let mut enabled = true;
-    let mut result = vec![];
-
-    for line in input {
-    for c in re.captures_iter(&*line) {
-        let m = c.get(0).unwrap().as_str(); // Unwrap: Guaranteed to be present for index 0
-        if m == DO {
-            enabled = true;
-            continue;
-        } else if m == DONT {
-            enabled = false;
-            continue;
-        }
-        let Some(b) = c.get(2) else {
-            panic!("regex matches missing after filtering commands: {}", m);
-        };
-        let b = b.as_str();
-        let a = c.get(1).unwrap().as_str();
-        if enabled || !part2 {
-            result.push((a.parse()?, b.parse()?))
+/// Accepts the usual day arguments, plus:
+///
+/// - `--stream` scans the input in chunks instead of reading it into memory.
+/// - `--wide` lifts the three digit limit on `mul` operands.
+/// - `--scope global|line|file` sets how far a `don't()` reaches.
+/// - `--explain` prints the input with the matched instructions highlighted.
+///
+/// Several input files may be given, which prints each file's share of the
+/// answers and then the totals. `--format json` only applies without any of
+/// these.
+fn main() -> anyhow::Result<()> {
+    let (mut stream, mut wide, mut explain) = (false, false, false);
+    let mut scope = None;
+    let mut opts = vec![];
+    let mut files = vec![];
+    let mut rest = common::log::init_from_args().into_iter();
+    while let Some(arg) = rest.next() {
+        match &*arg {
+            "--stream" => stream = true,
+            "--wide" => wide = true,
+            "--explain" => explain = true,
+            "--scope" => {
+                let v = rest.next().ok_or_else(|| anyhow!("--scope needs a value"))?;
+                scope = Some(v.parse()?);
+            }
+            "--part" | "--format" => {
+                opts.push(arg);
+                opts.extend(rest.next());
+            }
+            _ if arg.starts_with("--") => opts.push(arg),
+            _ => files.push(arg),
         }
     }
+    if !stream && !wide && !explain && scope.is_none() && files.len() <= 1 {
+        opts.extend(files);
+        return common::cli::main_with_args::<day03::Day03>(opts);
     }
-    Ok(result)
-}
-
-fn main() -> anyhow::Result<()> {
-    let part2 = true;
-    let input = std::io::stdin().lock().lines();
-    let input = input.collect::<Result<Vec<String>, _>>()?;
-
-    let pairs = find_muls(&input, part2)?;
-
-    println!(
-        "sum {}",
-        pairs
-            .iter()
-            .map(|(a, b)| a.checked_mul(*b).expect("overflow"))
-            .sum::<usize>()
-    );
-    Ok(())
-}
-
-#[cfg(test)]
-mod test {
-    use crate::*;
+    let scope = scope.unwrap_or(EnableScope::Global);
+    let args = Args::parse(opts)?;
+    let mode = if explain {
+        "--explain"
+    } else if stream {
+        "--stream"
+    } else if wide {
+        "--wide"
+    } else if files.len() > 1 {
+        "several inputs"
+    } else {
+        "--scope"
+    };
+    args.require_text(mode)?;
+    let print = |label: &str, sums: Sums| {
+        for (part, sum) in [(1, sums.part1), (2, sums.part2)] {
+            if args.parts.includes(part) {
+                println!("{}part{}: {}", label, part, sum);
+            }
+        }
+    };
 
-    #[test]
-    fn example() {
-        assert_eq!(
-            find_muls(
-                &[r"xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))".to_string()],
-                false
-            )
-            .unwrap(),
-            vec![(2, 4), (5, 5), (11, 8), (8, 5)]
-        );
+    if explain {
+        if files.len() > 1 {
+            bail!("--explain takes a single input");
+        }
+        let args = Args { input: files.pop().map(Into::into), ..args };
+        let input = args.read_input()?;
+        let ops = if wide { day03::WIDE_PART2 } else { day03::PART2 };
+        print!("{}", day03::explain(&input, &day03::instructions(&input, ops, scope)?));
+        return Ok(());
     }
 
-    #[test]
-    fn example2() {
-        assert_eq!(
-            find_muls(
-                &[r"xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))".to_string()],
-                true
-            )
-            .unwrap(),
-            vec![(2, 4), (8, 5)]
-        );
+    let mut acc = Accumulator::new(wide, scope);
+    if files.is_empty() {
+        acc.add(std::io::stdin().lock())?;
+    }
+    for file in &files {
+        let reader = std::fs::File::open(file).with_context(|| format!("reading {}", file))?;
+        let sums = acc.add(reader).with_context(|| format!("in {}", file))?;
+        if files.len() > 1 {
+            print(&format!("{}: ", file), sums);
+        }
     }
+    print(if files.len() > 1 { "total: " } else { "" }, acc.total());
+    Ok(())
 }
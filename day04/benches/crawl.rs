@@ -0,0 +1,39 @@
+//! Times `crawl` against `crawl_bits` on random grids of XMAS letters.
+//!
+//! Run with `cargo bench -p day04`.
+
+use common::testing::Rng;
+use grid::Grid;
+use std::time::{Duration, Instant};
+
+const ITERS: usize = 10;
+
+/// Median time of `ITERS` runs of `f`.
+fn median(mut f: impl FnMut() -> usize) -> (Duration, usize) {
+    let mut found = 0;
+    let mut samples = (0..ITERS)
+        .map(|_| {
+            let start = Instant::now();
+            found = std::hint::black_box(f());
+            start.elapsed()
+        })
+        .collect::<Vec<Duration>>();
+    samples.sort();
+    (samples[ITERS / 2], found)
+}
+
+fn main() -> anyhow::Result<()> {
+    let mut rng = Rng(0x5eed);
+    println!("{:>12}  {:>12}  {:>12}", "grid", "crawl", "crawl_bits");
+    for (rows, cols) in [(140, 140), (100, 20_000), (1_000, 1_000)] {
+        let cells = (0..rows)
+            .map(|_| (0..cols).map(|_| b"XMAS"[rng.below(4) as usize]).collect())
+            .collect();
+        let grid = Grid::from_rows(cells)?;
+        let (automaton, expected) = median(|| day04::crawl(&grid, &[b"XMAS"]).unwrap().len());
+        let (bits, found) = median(|| day04::crawl_bits(&grid, &[b"XMAS"]).unwrap().len());
+        anyhow::ensure!(found == expected, "crawl_bits found {} matches, crawl {}", found, expected);
+        println!("{:>12}  {:>12.2?}  {:>12.2?}", format!("{}x{}", rows, cols), automaton, bits);
+    }
+    Ok(())
+}
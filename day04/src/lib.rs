@@ -0,0 +1,879 @@
+use anyhow::bail;
+use common::Solution;
+use grid::{Grid, Offset, Pos, DOWN, RIGHT};
+use std::collections::{HashSet, VecDeque};
+
+const XMAS: &[u8] = b"XMAS";
+
+/// Two `MAS` crossing diagonally at the `A`.
+pub const X_MAS: &str = "M.S\n.A.\nM.S";
+/// Two `MAS` crossing orthogonally at the `A`.
+pub const PLUS_MAS: &str = ".M.\nMAS\n.S.";
+
+/// Which way a match runs; `true` if the word reads backwards along it.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Orient {
+    /// At the word's leftmost letter.
+    Horiz(bool),
+    /// At the word's bottom letter.
+    Vert(bool),
+    /// Down and to the right, at the top letter.
+    DiagForward(bool),
+    /// Down and to the left, at the top letter.
+    DiagBack(bool),
+    /// A [`Stencil`], at the top-left corner of the transformed template.
+    Stencil(Transform),
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Match {
+    pub row: usize,
+    pub col: usize,
+    pub orient: Orient,
+    /// Index into the searched words or stencils.
+    pub word: usize,
+}
+
+/// Aho-Corasick automaton finding every occurrence of a set of patterns in
+/// one pass, with the failure links folded into a dense transition table.
+struct Matcher {
+    /// Bytes not in any pattern share class 0.
+    classes: [u8; 256],
+    stride: usize,
+    /// Row `node * stride + class` is the node reached on that class of byte.
+    next: Vec<u32>,
+    /// Patterns ending at each node, including through failure links.
+    out: Vec<Vec<usize>>,
+    lens: Vec<usize>,
+}
+
+impl Matcher {
+    /// Marks a transition not in the trie, before the failure links are folded in.
+    const NONE: u32 = u32::MAX;
+
+    fn new<'a>(patterns: impl IntoIterator<Item = &'a [u8]> + Clone) -> Matcher {
+        let mut classes = [0u8; 256];
+        let mut stride = 1;
+        for &b in patterns.clone().into_iter().flatten() {
+            if classes[b as usize] == 0 {
+                classes[b as usize] = stride as u8;
+                stride += 1;
+            }
+        }
+        let mut m = Matcher {
+            classes,
+            stride,
+            next: vec![Matcher::NONE; stride],
+            out: vec![vec![]],
+            lens: vec![],
+        };
+        for (id, pat) in patterns.into_iter().enumerate() {
+            let mut node = 0;
+            for &b in pat {
+                let at = node * stride + m.classes[b as usize] as usize;
+                if m.next[at] == Matcher::NONE {
+                    m.next[at] = m.out.len() as u32;
+                    m.next.extend(std::iter::repeat_n(Matcher::NONE, stride));
+                    m.out.push(vec![]);
+                }
+                node = m.next[at] as usize;
+            }
+            m.out[node].push(id);
+            m.lens.push(pat.len());
+        }
+        // Breadth first, so every failure target is finished before it's used.
+        let mut fail = vec![0; m.out.len()];
+        let mut queue = VecDeque::new();
+        for class in 0..stride {
+            match m.next[class] {
+                Matcher::NONE => m.next[class] = 0,
+                child => queue.push_back(child as usize),
+            }
+        }
+        while let Some(node) = queue.pop_front() {
+            for class in 0..stride {
+                let child = m.next[node * stride + class];
+                let via_fail = m.next[fail[node] * stride + class];
+                if child == Matcher::NONE {
+                    m.next[node * stride + class] = via_fail;
+                } else {
+                    let child = child as usize;
+                    fail[child] = via_fail as usize;
+                    let inherited = m.out[fail[child]].clone();
+                    m.out[child].extend(inherited);
+                    queue.push_back(child);
+                }
+            }
+        }
+        m
+    }
+
+    /// Calls `found(start, pattern)` for every occurrence in `text`.
+    fn scan(&self, text: impl IntoIterator<Item = u8>, mut found: impl FnMut(usize, usize)) {
+        let mut node = 0;
+        for (i, b) in text.into_iter().enumerate() {
+            node = self.next[node * self.stride + self.classes[b as usize] as usize] as usize;
+            for &id in &self.out[node] {
+                found(i + 1 - self.lens[id], id);
+            }
+        }
+    }
+}
+
+/// Finds every occurrence of each of `words` in all eight directions.
+///
+/// A palindrome is reported twice at each place, once each way.
+pub fn crawl(grid: &Grid<u8>, words: &[&[u8]]) -> anyhow::Result<Vec<Match>> {
+    if words.iter().any(|w| w.is_empty()) {
+        bail!("can't search for an empty word");
+    }
+    // Pattern 2i is word i, 2i + 1 the same word reversed.
+    let reversed = words
+        .iter()
+        .map(|w| w.iter().rev().copied().collect())
+        .collect::<Vec<Vec<u8>>>();
+    let matcher = Matcher::new(words.iter().zip(&reversed).flat_map(|(w, r)| [*w, &r[..]]));
+
+    let mut ret = vec![];
+    // The cells of the current row, column or diagonal, reused for each.
+    let mut line = Vec::<Pos>::with_capacity(grid.rows().max(grid.cols()));
+    let mut scan = |orient: fn(bool) -> Orient, cells: &mut dyn Iterator<Item = Pos>| {
+        line.clear();
+        line.extend(cells);
+        matcher.scan(line.iter().map(|pos| grid[*pos]), |start, id| {
+            let word = id / 2;
+            // Vertical matches are anchored at the bottom.
+            let anchor = if orient(false) == Orient::Vert(false) {
+                start + words[word].len() - 1
+            } else {
+                start
+            };
+            let (row, col) = line[anchor];
+            ret.push(Match { row, col, orient: orient(id % 2 == 1), word });
+        });
+    };
+    for r in 0..grid.rows() {
+        scan(Orient::Horiz, &mut grid.ray((r, 0), RIGHT));
+    }
+    for c in 0..grid.cols() {
+        scan(Orient::Vert, &mut grid.ray((0, c), DOWN));
+    }
+    for mut diagonal in grid.diagonals() {
+        scan(Orient::DiagForward, &mut diagonal);
+    }
+    for mut diagonal in grid.anti_diagonals() {
+        scan(Orient::DiagBack, &mut diagonal);
+    }
+    Ok(ret)
+}
+
+/// Per-letter bitmasks of a grid: bit `c` of `masks[letter][row]` is set
+/// when that cell holds that letter. Rows are packed into `u64` words.
+struct Bitboard {
+    words: usize,
+    /// Indexed by byte; `None` for letters not in the grid.
+    masks: Vec<Option<Vec<Vec<u64>>>>,
+}
+
+impl Bitboard {
+    fn new(grid: &Grid<u8>) -> Bitboard {
+        let words = grid.cols().div_ceil(64);
+        let mut masks = vec![None; 256];
+        for (row, cells) in grid.rows_iter().enumerate() {
+            for (col, &b) in cells.iter().enumerate() {
+                let rows = masks[b as usize].get_or_insert_with(|| vec![vec![0u64; words]; grid.rows()]);
+                rows[row][col / 64] |= 1 << (col % 64);
+            }
+        }
+        Bitboard { words, masks }
+    }
+
+    /// The row mask for `letter`, if it appears anywhere.
+    fn row(&self, letter: u8, row: usize) -> Option<&[u64]> {
+        self.masks[letter as usize].as_ref().map(|rows| &rows[row][..])
+    }
+}
+
+/// `acc &= src` shifted so that bit `c + shift` of `src` lines up with bit
+/// `c` of `acc`. Bits shifted in from outside `src` are clear.
+fn and_shifted(acc: &mut [u64], src: &[u64], shift: isize) {
+    let words = (shift.unsigned_abs() / 64) as isize * shift.signum();
+    let bits = shift.unsigned_abs() % 64;
+    let word = |i: isize| {
+        usize::try_from(i).ok().and_then(|i| src.get(i)).copied().unwrap_or(0)
+    };
+    for (i, a) in acc.iter_mut().enumerate() {
+        let i = i as isize + words;
+        let v = match (bits, shift > 0) {
+            (0, _) => word(i),
+            (_, true) => (word(i) >> bits) | (word(i + 1) << (64 - bits)),
+            (_, false) => (word(i) << bits) | (word(i - 1) >> (64 - bits)),
+        };
+        *a &= v;
+    }
+}
+
+/// Finds the same matches as [`crawl`], but a whole row of start cells at a
+/// time by ANDing together shifted per-letter bitmasks. Much faster on wide
+/// grids, and the output is in a different order.
+pub fn crawl_bits(grid: &Grid<u8>, words: &[&[u8]]) -> anyhow::Result<Vec<Match>> {
+    if words.iter().any(|w| w.is_empty()) {
+        bail!("can't search for an empty word");
+    }
+    let board = Bitboard::new(grid);
+    let axes = [
+        (Orient::Horiz as fn(bool) -> Orient, RIGHT),
+        (Orient::Vert, DOWN),
+        (Orient::DiagForward, (1, 1)),
+        (Orient::DiagBack, (1, -1)),
+    ];
+    let mut acc = vec![0u64; board.words];
+    let mut ret = vec![];
+    for (word, letters) in words.iter().enumerate() {
+        let reversed = letters.iter().rev().copied().collect::<Vec<u8>>();
+        let last = letters.len() - 1;
+        for (backwards, pattern) in [(false, *letters), (true, &reversed[..])] {
+            for (orient, (dr, dc)) in axes {
+                let span = last * dr as usize;
+                for row in 0..grid.rows().saturating_sub(span) {
+                    acc.fill(u64::MAX);
+                    let found = pattern.iter().enumerate().all(|(k, &b)| {
+                        let Some(mask) = board.row(b, row + k * dr as usize) else {
+                            return false;
+                        };
+                        and_shifted(&mut acc, mask, k as isize * dc);
+                        acc.iter().any(|&a| a != 0)
+                    });
+                    if !found {
+                        continue;
+                    }
+                    // Vertical matches are anchored at the bottom.
+                    let anchor = row + span * usize::from(dc == 0);
+                    for (i, &bits) in acc.iter().enumerate() {
+                        let mut bits = bits;
+                        while bits != 0 {
+                            let col = i * 64 + bits.trailing_zeros() as usize;
+                            bits &= bits - 1;
+                            ret.push(Match { row: anchor, col, orient: orient(backwards), word });
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(ret)
+}
+
+/// Which edges of the grid join up, so that words can run across them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Topology {
+    /// No edges join.
+    Plane,
+    /// The left and right edges join.
+    Cylinder,
+    /// The left and right edges join, and so do the top and bottom.
+    Torus,
+}
+
+impl std::str::FromStr for Topology {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "plane" => Ok(Topology::Plane),
+            "cylinder" => Ok(Topology::Cylinder),
+            "torus" => Ok(Topology::Torus),
+            _ => Err(anyhow::anyhow!("invalid topology {:?}, expected plane, cylinder or torus", s)),
+        }
+    }
+}
+
+impl Topology {
+    /// The cell `k` steps from `start` along `(dr, dc)`, if still on the grid.
+    fn step(self, grid: &Grid<u8>, (row, col): Pos, (dr, dc): Offset, k: usize) -> Option<Pos> {
+        let wrap = |x: usize, d: isize, n: usize, wraps: bool| {
+            let x = x as isize + d * k as isize;
+            if wraps {
+                Some(x.rem_euclid(n as isize) as usize)
+            } else {
+                usize::try_from(x).ok().filter(|&x| x < n)
+            }
+        };
+        Some((
+            wrap(row, dr, grid.rows(), self == Topology::Torus)?,
+            wrap(col, dc, grid.cols(), self != Topology::Plane)?,
+        ))
+    }
+}
+
+/// Like [`crawl`], but with words running on across the edges joined by
+/// `topology`. Anchors are where they would be were the grid repeated, so a
+/// horizontal match may start near the right edge and end near the left.
+///
+/// On grids smaller than a word, matches that wrap along different axes can
+/// read the same cells in the same order; each such path is only reported
+/// once.
+pub fn crawl_wrapped(grid: &Grid<u8>, words: &[&[u8]], topology: Topology) -> anyhow::Result<Vec<Match>> {
+    if topology == Topology::Plane {
+        return crawl_bits(grid, words);
+    }
+    if words.iter().any(|w| w.is_empty()) {
+        bail!("can't search for an empty word");
+    }
+    let axes = [
+        (Orient::Horiz as fn(bool) -> Orient, RIGHT),
+        (Orient::Vert, DOWN),
+        (Orient::DiagForward, (1, 1)),
+        (Orient::DiagBack, (1, -1)),
+    ];
+    let mut seen = HashSet::new();
+    let mut ret = vec![];
+    // Matches that don't cross an edge go first, so they're always kept.
+    for (wrapping, (word, letters)) in [false, true]
+        .into_iter()
+        .flat_map(|wrapping| words.iter().enumerate().map(move |w| (wrapping, w)))
+    {
+        let last = letters.len() - 1;
+        for (orient, dir) in axes {
+            for backwards in [false, true] {
+                for start in grid.positions() {
+                    if Topology::Plane.step(grid, start, dir, last).is_none() != wrapping {
+                        continue;
+                    }
+                    let path = (0..letters.len())
+                        .map(|k| topology.step(grid, start, dir, k))
+                        .collect::<Option<Vec<Pos>>>();
+                    let Some(mut path) = path else { continue };
+                    if backwards {
+                        path.reverse();
+                    }
+                    if !path.iter().zip(*letters).all(|(&pos, &b)| grid[pos] == b) {
+                        continue;
+                    }
+                    // Vertical matches are anchored at the bottom.
+                    let (row, col) = if orient(false) == Orient::Vert(false) {
+                        topology.step(grid, start, dir, last).unwrap()
+                    } else {
+                        start
+                    };
+                    if seen.insert((word, path)) || !wrapping {
+                        ret.push(Match { row, col, orient: orient(backwards), word });
+                    }
+                }
+            }
+        }
+    }
+    Ok(ret)
+}
+
+/// A rotation by `turns` quarter turns clockwise, after flipping left to
+/// right if `mirrored`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Transform {
+    pub mirrored: bool,
+    pub turns: u8,
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform { mirrored: false, turns: 0 };
+
+    /// All eight rotations and reflections.
+    pub fn all() -> impl Iterator<Item = Transform> {
+        [false, true]
+            .into_iter()
+            .flat_map(|mirrored| (0..4).map(move |turns| Transform { mirrored, turns }))
+    }
+
+    pub fn apply<T: Clone>(&self, grid: &Grid<T>) -> Grid<T> {
+        let mut out = if self.mirrored { grid.mirrored() } else { grid.clone() };
+        for _ in 0..self.turns {
+            out = out.rotated();
+        }
+        out
+    }
+}
+
+impl std::fmt::Display for Transform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.mirrored {
+            write!(f, "mirrored, ")?;
+        }
+        write!(f, "rotated {}", u32::from(self.turns) * 90)
+    }
+}
+
+/// A 2D shape to search for, with `.` for cells that match anything.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Stencil {
+    /// The distinct orientations of the template, with the first transform
+    /// giving each.
+    variants: Vec<(Transform, Grid<Option<u8>>)>,
+}
+
+impl Stencil {
+    /// Parses an ASCII template, one row per line.
+    pub fn parse(template: &str) -> anyhow::Result<Stencil> {
+        let grid = Grid::parse(template, |c, _| Ok((c != b'.').then_some(c)))?;
+        if grid.iter().all(|(_, c)| c.is_none()) {
+            bail!("stencil has no letters");
+        }
+        let mut variants = Vec::<(Transform, Grid<Option<u8>>)>::new();
+        for t in Transform::all() {
+            let variant = t.apply(&grid);
+            if variants.iter().all(|(_, v)| *v != variant) {
+                variants.push((t, variant));
+            }
+        }
+        Ok(Stencil { variants })
+    }
+
+    fn matches_at(variant: &Grid<Option<u8>>, grid: &Grid<u8>, (row, col): Pos) -> bool {
+        variant.iter().all(|((r, c), cell)| match cell {
+            Some(want) => grid.get((row + r, col + c)) == Some(want),
+            None => true,
+        })
+    }
+}
+
+/// Finds every placement of each of `stencils` under any rotation or
+/// reflection. Symmetric stencils are only reported once per placement.
+pub fn crawl_stencils(grid: &Grid<u8>, stencils: &[Stencil]) -> Vec<Match> {
+    let mut ret = vec![];
+    for (word, stencil) in stencils.iter().enumerate() {
+        for (transform, variant) in &stencil.variants {
+            let rows = (grid.rows() + 1).saturating_sub(variant.rows());
+            let cols = (grid.cols() + 1).saturating_sub(variant.cols());
+            for row in 0..rows {
+                for col in 0..cols {
+                    if Stencil::matches_at(variant, grid, (row, col)) {
+                        ret.push(Match { row, col, orient: Orient::Stencil(*transform), word });
+                    }
+                }
+            }
+        }
+    }
+    ret
+}
+
+pub struct Day04;
+
+impl Solution for Day04 {
+    const DAY: u32 = 4;
+
+    type Input = Grid<u8>;
+    type Answer = usize;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        Grid::parse_bytes(input)
+    }
+
+    fn part1(input: &Self::Input) -> anyhow::Result<usize> {
+        Ok(crawl_bits(input, &[XMAS])?.len())
+    }
+
+    fn part2(input: &Self::Input) -> anyhow::Result<usize> {
+        Ok(crawl_stencils(input, &[Stencil::parse(X_MAS)?]).len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use common::testing::Rng;
+
+    fn x_mas() -> Stencil {
+        Stencil::parse(X_MAS).unwrap()
+    }
+
+    fn turns(turns: u8) -> Transform {
+        Transform { mirrored: false, turns }
+    }
+
+    #[test]
+    fn example1() {
+        let data = r"..X...X
+.SAMXM.
+.A..A..
+XMAS.S.
+.X.....";
+        let mut res = crawl(&Grid::parse_bytes(data).unwrap(), &[XMAS]).unwrap();
+        res.sort();
+        assert_eq!(
+            res,
+            vec![
+                Match {
+                    row: 0,
+                    col: 2,
+                    orient: Orient::DiagForward(false),
+                    word: 0
+                },
+                Match {
+                    row: 0,
+                    col: 6,
+                    orient: Orient::DiagBack(false),
+                    word: 0
+                },
+                Match {
+                    row: 1,
+                    col: 1,
+                    orient: Orient::Horiz(true),
+                    word: 0
+                },
+                Match {
+                    row: 3,
+                    col: 0,
+                    orient: Orient::Horiz(false),
+                    word: 0
+                },
+                Match {
+                    row: 4,
+                    col: 1,
+                    orient: Orient::Vert(true),
+                    word: 0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn example2() {
+        let data = r"MMMSXXMASM
+MSAMXMSMSA
+AMXSXMAAMM
+MSAMASMSMX
+XMASAMXAMM
+XXAMMXXAMA
+SMSMSASXSS
+SAXAMASAAA
+MAMMMXMMMM
+MXMXAXMASX
+";
+        let mut res = crawl(&Grid::parse_bytes(data).unwrap(), &[XMAS]).unwrap();
+        res.sort();
+        assert_eq!(res.len(), 18);
+    }
+
+    #[test]
+    fn example3() {
+        let data = r"M.S
+.A.
+M.S
+";
+        let mut res = crawl_stencils(&Grid::parse_bytes(data).unwrap(), &[x_mas()]);
+        res.sort();
+        assert_eq!(res, vec![Match{row: 0, col: 0, orient: Orient::Stencil(Transform::IDENTITY), word: 0}]);
+    }
+    #[test]
+    fn example4() {
+        let data = r"MMMSXXMASM
+MSAMXMSMSA
+AMXSXMAAMM
+MSAMASMSMX
+XMASAMXAMM
+XXAMMXXAMA
+SMSMSASXSS
+SAXAMASAAA
+MAMMMXMMMM
+MXMXAXMASX
+";
+        let mut res = crawl_stencils(&Grid::parse_bytes(data).unwrap(), &[x_mas()]);
+        res.sort();
+        assert_eq!(res.len(), 9);
+    }
+
+    #[test]
+    fn word_list() {
+        let data = "CATS\nXAXX\nXXTX\nSTAC";
+        let grid = Grid::parse_bytes(data).unwrap();
+        let mut res = crawl(&grid, &[b"CAT", b"CATS", b"DOG"]).unwrap();
+        res.sort();
+        assert_eq!(
+            res,
+            vec![
+                Match { row: 0, col: 0, orient: Orient::Horiz(false), word: 0 },
+                Match { row: 0, col: 0, orient: Orient::Horiz(false), word: 1 },
+                Match { row: 0, col: 0, orient: Orient::DiagForward(false), word: 0 },
+                Match { row: 3, col: 0, orient: Orient::Horiz(true), word: 1 },
+                Match { row: 3, col: 1, orient: Orient::Horiz(true), word: 0 },
+            ]
+        );
+        assert!(crawl(&grid, &[b""]).is_err());
+    }
+
+    #[test]
+    fn palindromes_and_single_letters() {
+        let grid = Grid::parse_bytes("ABA").unwrap();
+        assert_eq!(crawl(&grid, &[b"ABA"]).unwrap().len(), 2);
+        // Every letter is found along each of the four axes, both ways.
+        assert_eq!(crawl(&grid, &[b"B"]).unwrap().len(), 8);
+    }
+
+    /// Counts each word by walking from every cell in every direction.
+    fn brute_force(grid: &Grid<u8>, words: &[&[u8]]) -> Vec<usize> {
+        words
+            .iter()
+            .map(|word| {
+                grid.positions()
+                    .flat_map(|start| grid::DIRS8.map(|dir| (start, dir)))
+                    .filter(|&(start, dir)| {
+                        let cells = grid.ray(start, dir).take(word.len());
+                        cells.map(|pos| grid[pos]).eq(word.iter().copied())
+                    })
+                    .count()
+            })
+            .collect()
+    }
+
+    fn random_grid(rng: &mut Rng, rows: usize, cols: usize, letters: &[u8]) -> Grid<u8> {
+        let cells = (0..rows)
+            .map(|_| (0..cols).map(|_| letters[rng.below(letters.len() as u64) as usize]).collect())
+            .collect();
+        Grid::from_rows(cells).unwrap()
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut rng = Rng(0x2024_1204);
+        let words: [&[u8]; 5] = [b"AB", b"ABA", b"BAB", b"ABCA", b"CC"];
+        for _ in 0..200 {
+            let rows = 1 + rng.below(7) as usize;
+            let cols = 1 + rng.below(7) as usize;
+            let grid = random_grid(&mut rng, rows, cols, b"ABC");
+            let res = crawl(&grid, &words).unwrap();
+            let counts = (0..words.len())
+                .map(|w| res.iter().filter(|m| m.word == w).count())
+                .collect::<Vec<usize>>();
+            assert_eq!(counts, brute_force(&grid, &words));
+        }
+    }
+
+    #[test]
+    fn bits_matches_crawl() {
+        let words: [&[u8]; 6] = [b"AB", b"ABA", b"BAB", b"ABCA", b"CC", b"B"];
+        // Wide enough to cross several 64-bit words.
+        let mut rng = Rng(0x2024_1204);
+        for _ in 0..200 {
+            let rows = 1 + rng.below(9) as usize;
+            let cols = 1 + rng.below(200) as usize;
+            let grid = random_grid(&mut rng, rows, cols, b"ABC");
+            let mut expected = crawl(&grid, &words).unwrap();
+            let mut res = crawl_bits(&grid, &words).unwrap();
+            expected.sort();
+            res.sort();
+            assert_eq!(res, expected);
+        }
+        let long = b"XMASXMASXMASXMASXMASXMASXMASXMASXMASXMASXMASXMASXMASXMASXMASXMASXMASX";
+        let grid = Grid::parse_bytes(std::str::from_utf8(&long[..]).unwrap()).unwrap();
+        assert_eq!(crawl_bits(&grid, &[&long[..], XMAS]).unwrap().len(), 1 + 17);
+        assert!(crawl_bits(&grid, &[b""]).is_err());
+    }
+
+    #[test]
+    fn bits_matches_crawl_on_large_grids() {
+        let mut rng = Rng(0x5eed);
+        for (rows, cols) in [(140, 140), (20, 5_000)] {
+            let grid = random_grid(&mut rng, rows, cols, b"XMAS");
+            assert_eq!(crawl_bits(&grid, &[XMAS]).unwrap().len(), crawl(&grid, &[XMAS]).unwrap().len());
+        }
+    }
+
+    #[test]
+    fn wrapping_across_edges() {
+        let data = "MASX\nX...\nS..M\nA...";
+        let grid = Grid::parse_bytes(data).unwrap();
+        assert!(crawl(&grid, &[XMAS]).unwrap().is_empty());
+        assert_eq!(
+            crawl_wrapped(&grid, &[XMAS], Topology::Cylinder).unwrap(),
+            vec![Match { row: 0, col: 3, orient: Orient::Horiz(false), word: 0 }]
+        );
+        let mut res = crawl_wrapped(&grid, &[XMAS], Topology::Torus).unwrap();
+        res.sort();
+        assert_eq!(
+            res,
+            vec![
+                Match { row: 0, col: 3, orient: Orient::Horiz(false), word: 0 },
+                Match { row: 1, col: 0, orient: Orient::Vert(true), word: 0 },
+            ]
+        );
+        assert!(crawl_wrapped(&grid, &[b""], Topology::Torus).is_err());
+        assert_eq!("torus".parse::<Topology>().unwrap(), Topology::Torus);
+        assert!("sphere".parse::<Topology>().is_err());
+    }
+
+    #[test]
+    fn wrapping_fully_around() {
+        // Every axis reads the one row left to right.
+        let grid = Grid::parse_bytes("XMAS").unwrap();
+        for topology in [Topology::Cylinder, Topology::Torus] {
+            assert_eq!(
+                crawl_wrapped(&grid, &[XMAS, b"SXMASX"], topology).unwrap(),
+                vec![
+                    Match { row: 0, col: 0, orient: Orient::Horiz(false), word: 0 },
+                    Match { row: 0, col: 3, orient: Orient::Horiz(false), word: 1 },
+                ]
+            );
+        }
+        // Both ways along a two-cell loop read the same cells in order.
+        let grid = Grid::parse_bytes("X\nM").unwrap();
+        assert_eq!(
+            crawl_wrapped(&grid, &[b"XMXM"], Topology::Torus).unwrap(),
+            vec![Match { row: 1, col: 0, orient: Orient::Vert(false), word: 0 }]
+        );
+        // Unwrapped matches are kept as they are, single letters included.
+        assert_eq!(crawl_wrapped(&grid, &[b"X"], Topology::Torus).unwrap().len(), 8);
+    }
+
+    /// Counts each word by walking from every cell in every direction,
+    /// wrapping at all edges.
+    fn brute_force_torus(grid: &Grid<u8>, words: &[&[u8]]) -> Vec<usize> {
+        let (rows, cols) = (grid.rows() as isize, grid.cols() as isize);
+        words
+            .iter()
+            .map(|word| {
+                grid.positions()
+                    .flat_map(|start| grid::DIRS8.map(|dir| (start, dir)))
+                    .filter(|&((r, c), (dr, dc))| {
+                        word.iter().enumerate().all(|(k, &b)| {
+                            let r = (r as isize + dr * k as isize).rem_euclid(rows);
+                            let c = (c as isize + dc * k as isize).rem_euclid(cols);
+                            grid[(r as usize, c as usize)] == b
+                        })
+                    })
+                    .count()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn wrapping_finds_everything_flat() {
+        let words: [&[u8]; 4] = [b"AB", b"ABA", b"ABCA", b"CC"];
+        let mut rng = Rng(0x7025);
+        for _ in 0..200 {
+            let rows = 1 + rng.below(7) as usize;
+            let cols = 1 + rng.below(7) as usize;
+            let grid = random_grid(&mut rng, rows, cols, b"ABC");
+            let plane = crawl(&grid, &words).unwrap();
+            let cylinder = crawl_wrapped(&grid, &words, Topology::Cylinder).unwrap();
+            let torus = crawl_wrapped(&grid, &words, Topology::Torus).unwrap();
+            assert!(plane.iter().all(|m| cylinder.contains(m)));
+            assert!(plane.iter().all(|m| torus.contains(m)));
+            // Grids at least as big as the words can't repeat a path.
+            if rows >= 4 && cols >= 4 {
+                let counts = (0..words.len())
+                    .map(|w| torus.iter().filter(|m| m.word == w).count())
+                    .collect::<Vec<usize>>();
+                assert_eq!(counts, brute_force_torus(&grid, &words));
+            }
+        }
+    }
+
+    #[test]
+    fn wide_grid() {
+        let data = "\
+M.S.....XMAS.M.M
+.A............A.
+M.S..SAMX....S.S";
+        let grid = Grid::parse_bytes(data).unwrap();
+        assert_eq!((grid.rows(), grid.cols()), (3, 16));
+        let mut res = crawl_stencils(&grid, &[x_mas()]);
+        res.sort();
+        assert_eq!(
+            res,
+            vec![
+                Match { row: 0, col: 0, orient: Orient::Stencil(Transform::IDENTITY), word: 0 },
+                Match { row: 0, col: 13, orient: Orient::Stencil(turns(1)), word: 0 },
+            ]
+        );
+        assert_eq!(crawl(&grid, &[XMAS]).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn tall_grid() {
+        let data = "X..\nM..\nA..\nS..\n...\nS.M\n.A.\nS.M\nX..\n";
+        let grid = Grid::parse_bytes(data).unwrap();
+        assert_eq!((grid.rows(), grid.cols()), (9, 3));
+        assert_eq!(
+            crawl_stencils(&grid, &[x_mas()]),
+            vec![Match { row: 5, col: 0, orient: Orient::Stencil(turns(2)), word: 0 }]
+        );
+        assert_eq!(crawl(&grid, &[XMAS]).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn tiny_grids() {
+        for data in ["", "A", "MAS", "M\nA\nS", "M.\n.A"] {
+            let grid = Grid::parse_bytes(data).unwrap();
+            assert!(crawl_stencils(&grid, &[x_mas()]).is_empty(), "{:?}", data);
+            assert!(crawl(&grid, &[XMAS]).unwrap().is_empty(), "{:?}", data);
+        }
+    }
+
+    #[test]
+    fn no_trailing_newline() {
+        let data = "MMMSXXMASM\nMSAMXMSMSA\nAMXSXMAAMM\nMSAMASMSMX\nXMASAMXAMM\n\
+                    XXAMMXXAMA\nSMSMSASXSS\nSAXAMASAAA\nMAMMMXMMMM\nMXMXAXMASX";
+        let grid = Day04::parse(data).unwrap();
+        assert_eq!(grid, Day04::parse(&format!("{}\n", data)).unwrap());
+        assert_eq!(Day04::part1(&grid).unwrap(), 18);
+        assert_eq!(Day04::part2(&grid).unwrap(), 9);
+    }
+
+    #[test]
+    fn stencil_variants() {
+        // The X-MAS is symmetric under a mirror, so has four distinct variants.
+        assert_eq!(x_mas().variants.len(), 4);
+        assert_eq!(Stencil::parse(PLUS_MAS).unwrap().variants.len(), 4);
+        assert_eq!(Stencil::parse("AB\n.C").unwrap().variants.len(), 8);
+        assert_eq!(Stencil::parse("A").unwrap().variants.len(), 1);
+        assert!(Stencil::parse("..\n..").is_err());
+        assert!(Stencil::parse("AB\nC").is_err());
+    }
+
+    #[test]
+    fn stencil_transforms() {
+        let data = "\
+.M...
+MAS..
+.S...
+..M.S
+...A.
+..M.S";
+        let grid = Grid::parse_bytes(data).unwrap();
+        let stencils = [Stencil::parse(PLUS_MAS).unwrap(), x_mas()];
+        let mut res = crawl_stencils(&grid, &stencils);
+        res.sort();
+        assert_eq!(
+            res,
+            vec![
+                Match { row: 0, col: 0, orient: Orient::Stencil(Transform::IDENTITY), word: 0 },
+                Match { row: 3, col: 2, orient: Orient::Stencil(Transform::IDENTITY), word: 1 },
+            ]
+        );
+
+        // An L shape, only found mirrored.
+        let stencil = Stencil::parse("X.\nXY").unwrap();
+        let grid = Grid::parse_bytes("..\n.X\nYX").unwrap();
+        let res = crawl_stencils(&grid, &[stencil]);
+        let Orient::Stencil(t) = res[0].orient else { panic!() };
+        assert_eq!((res.len(), res[0].row, res[0].col), (1, 1, 0));
+        assert_eq!(t.apply(&Grid::parse_bytes("X.\nXY").unwrap()), Grid::parse_bytes(".X\nYX").unwrap());
+        assert_eq!(t.to_string(), "mirrored, rotated 0");
+    }
+
+    #[test]
+    fn xmas_stencil_matches_all_transforms() {
+        // Every orientation of the X-MAS shows up in the example.
+        let data = "MMMSXXMASM\nMSAMXMSMSA\nAMXSXMAAMM\nMSAMASMSMX\nXMASAMXAMM\n\
+                    XXAMMXXAMA\nSMSMSASXSS\nSAXAMASAAA\nMAMMMXMMMM\nMXMXAXMASX";
+        let res = crawl_stencils(&Day04::parse(data).unwrap(), &[x_mas()]);
+        let mut transforms = res
+            .iter()
+            .map(|m| match m.orient {
+                Orient::Stencil(t) => t,
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        transforms.dedup();
+        assert_eq!(transforms, (0..4).map(turns).collect::<Vec<_>>());
+    }
+}
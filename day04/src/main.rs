@@ -1,260 +1,77 @@
-use std::io::Read;
-
-const L_X: u8 = b'X';
-const L_M: u8 = b'M';
-const L_A: u8 = b'A';
-const L_S: u8 = b'S';
-
-const FORWARD: u32 =
-    ((L_X as u32) << 24) | ((L_M as u32) << 16) | ((L_A as u32) << 8) | (L_S as u32);
-const REVERSE: u32 =
-    ((L_S as u32) << 24) | ((L_A as u32) << 16) | ((L_M as u32) << 8) | (L_X as u32);
-
-#[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
-enum Orient {
-    Horiz(bool),
-    Vert(bool),
-    DiagForward(bool),
-    DiagBack(bool),
-    XMas(bool, bool),
-}
-
-#[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
-struct Match {
-    row: usize,
-    col: usize,
-    orient: Orient,
-}
-
-#[derive(Clone, Debug)]
-struct Crawler(u32);
-
-impl Crawler {
-    fn push(&mut self, data: u8) -> Option<bool> {
-        self.0 = (self.0 << 8) | u32::from(data);
-        if self.0 == FORWARD {
-            Some(false)
-        } else if self.0 == REVERSE {
-            Some(true)
-        } else {
-            None
-        }
-    }
-}
-
-fn crawl(data: &[u8], part2: bool) -> anyhow::Result<Vec<Match>> {
-    // including newline byte
-    let linelen = data.iter().position(|c| *c == b'\n').unwrap();
-    let linebytes = linelen + 1;
-    let linecount = {
-        let n = (data.trim_ascii_end().len() + 1).div_ceil(linebytes);
-        for i in 0..n {
-            let pos = i * linebytes + linelen;
-            if data.get(pos).copied() != Some(b'\n') && !(i == (n - 1) && data.get(pos) == None) {
-                return Err(anyhow::anyhow!(
-                    "missing newline at byte {}. linelen={} linecount={} actual={:x?}",
-                    pos,
-                    linelen,
-                    n,
-                    data.get(pos).copied()
-                ));
+use anyhow::{anyhow, Context};
+use common::cli::Args;
+use common::Solution;
+use day04::{Day04, Orient, Stencil, Topology, Transform};
+use std::collections::BTreeMap;
+
+/// Accepts the usual day arguments, plus `--words W1,W2,...` and
+/// `--dict PATH` (one word per line) to search for those words instead,
+/// printing how often each was found. `--stencil PATH` searches for the 2D
+/// template in that file instead, printing how often each transform matched.
+/// `--wrap plane|cylinder|torus` lets words run across the grid's edges.
+fn main() -> anyhow::Result<()> {
+    let mut words = vec![];
+    let mut stencil = None;
+    let mut topology = None;
+    let mut opts = vec![];
+    let mut rest = common::log::init_from_args().into_iter();
+    while let Some(arg) = rest.next() {
+        match &*arg {
+            "--words" => {
+                let v = rest.next().ok_or_else(|| anyhow!("--words needs a value"))?;
+                words.extend(v.split(',').map(String::from));
             }
-        }
-        n
-    };
-
-    let get = |row, col| data.get(row * linebytes + col).map(|c| *c);
-    let mut ret = vec![];
-
-    if part2 {
-        for rowi in 1..(linecount-1) {
-            for coli in 1..(linecount-1) {
-                if get(rowi, coli) != Some(L_A) {
-                    continue;
-                }
-                let tl = get(rowi-1, coli-1);
-                let dr = get(rowi+1, coli+1);
-                let tr = get(rowi-1, coli+1);
-                let dl = get(rowi+1, coli-1);
-                let tldr: bool = if tl == Some(L_M) && dr == Some(L_S) {
-                    false
-                } else if tl == Some(L_S) && dr == Some(L_M) {
-                    true
-                } else {
-                    continue;
-                };
-                let trdl: bool = if dl == Some(L_M) && tr == Some(L_S) {
-                    false
-                } else if dl == Some(L_S) && tr == Some(L_M) {
-                    true
-                } else {
-                    continue;
-                };
-                ret.push(Match {
-                    row: rowi,
-                    col: coli,
-                    orient: Orient::XMas(tldr, trdl),
-                });
+            "--dict" => {
+                let path = rest.next().ok_or_else(|| anyhow!("--dict needs a path"))?;
+                let dict = std::fs::read_to_string(&path)
+                    .with_context(|| format!("reading {}", path))?;
+                words.extend(dict.lines().filter(|l| !l.is_empty()).map(String::from));
             }
-        }
-    } else {
-        let mut colcrawl = std::iter::repeat_n(Crawler(0), linelen).collect::<Vec<_>>();
-
-        for rowi in 0..linecount {
-            let mut rowcrawl = Crawler(0);
-            for coli in 0..linelen {
-                let c = get(rowi, coli).ok_or_else(|| {
-                    anyhow::anyhow!("fail to fetch byte row {} col {}", rowi, coli)
-                })?;
-                if let Some(dir) = rowcrawl.push(c) {
-                    ret.push(Match {
-                        row: rowi,
-                        col: coli.checked_sub(3).unwrap(),
-                        orient: Orient::Horiz(dir),
-                    });
-                }
-                if let Some(dir) = colcrawl.get_mut(coli).unwrap().push(c) {
-                    ret.push(Match {
-                        row: rowi,
-                        col: coli,
-                        orient: Orient::Vert(dir),
-                    });
-                }
-                if (rowi + 3) < linecount && (coli + 3) < linelen {
-                    let mut diag = Crawler(0);
-                    diag.push(c);
-                    diag.push(get(rowi + 1, coli + 1).unwrap());
-                    diag.push(get(rowi + 2, coli + 2).unwrap());
-                    if let Some(dir) = diag.push(get(rowi + 3, coli + 3).unwrap()) {
-                        ret.push(Match {
-                            row: rowi,
-                            col: coli,
-                            orient: Orient::DiagForward(dir),
-                        });
-                    }
-                }
-                if (rowi + 3) < linecount && ((coli as isize) - 3) >= 0 {
-                    let mut diag = Crawler(0);
-                    diag.push(c);
-                    diag.push(get(rowi + 1, coli - 1).unwrap());
-                    diag.push(get(rowi + 2, coli - 2).unwrap());
-                    if let Some(dir) = diag.push(get(rowi + 3, coli - 3).unwrap()) {
-                        ret.push(Match {
-                            row: rowi,
-                            col: coli,
-                            orient: Orient::DiagBack(dir),
-                        });
-                    }
-                }
+            "--stencil" => {
+                let path = rest.next().ok_or_else(|| anyhow!("--stencil needs a path"))?;
+                let template = std::fs::read_to_string(&path)
+                    .with_context(|| format!("reading {}", path))?;
+                stencil = Some(Stencil::parse(&template).with_context(|| format!("parsing {}", path))?);
             }
+            "--wrap" => {
+                let v = rest.next().ok_or_else(|| anyhow!("--wrap needs a value"))?;
+                topology = Some(v.parse()?);
+            }
+            _ => opts.push(arg),
         }
-        println!("colcrawl: {:x?}", colcrawl);
     }
-
-    Ok(ret)
-}
-
-fn main() -> Result<(), anyhow::Error> {
-    let mut input = std::io::stdin().lock();
-    let mut data = Vec::new();
-    input.read_to_end(&mut data)?;
-    let data = data;
-
-    let res = crawl(&data, true)?;
-    println!("{}", res.len());
-    Ok(())
-}
-
-#[cfg(test)]
-mod test {
-    use super::*;
-
-    #[test]
-    fn example1() {
-        let data = r"..X...X
-.SAMXM.
-.A..A..
-XMAS.S.
-.X.....";
-        let mut res = crawl(data.as_bytes(), false).unwrap();
-        res.sort();
-        assert_eq!(
-            res,
-            vec![
-                Match {
-                    row: 0,
-                    col: 2,
-                    orient: Orient::DiagForward(false)
-                },
-                Match {
-                    row: 0,
-                    col: 6,
-                    orient: Orient::DiagBack(false)
-                },
-                Match {
-                    row: 1,
-                    col: 1,
-                    orient: Orient::Horiz(true)
-                },
-                Match {
-                    row: 3,
-                    col: 0,
-                    orient: Orient::Horiz(false)
-                },
-                Match {
-                    row: 4,
-                    col: 1,
-                    orient: Orient::Vert(true)
-                },
-            ]
-        );
+    if words.is_empty() && stencil.is_none() && topology.is_none() {
+        return common::cli::main_with_args::<Day04>(opts);
     }
-
-    #[test]
-    fn example2() {
-        let data = r"MMMSXXMASM
-MSAMXMSMSA
-AMXSXMAAMM
-MSAMASMSMX
-XMASAMXAMM
-XXAMMXXAMA
-SMSMSASXSS
-SAXAMASAAA
-MAMMMXMMMM
-MXMXAXMASX
-";
-        let mut res = crawl(data.as_bytes(), false).unwrap();
-        res.sort();
-        println!("{:?}", res);
-        assert_eq!(res.len(), 18);
+    let args = Args::parse(opts)?;
+    args.require_input_only(if stencil.is_some() { "--stencil" } else { "word searches" })?;
+    let grid = Day04::parse(&args.read_input()?)?;
+    if let Some(stencil) = stencil {
+        let matches = day04::crawl_stencils(&grid, &[stencil]);
+        let mut counts = BTreeMap::<Transform, usize>::new();
+        for m in &matches {
+            if let Orient::Stencil(t) = m.orient {
+                *counts.entry(t).or_default() += 1;
+            }
+        }
+        for (t, count) in counts {
+            println!("{}: {}", t, count);
+        }
+        println!("total: {}", matches.len());
+        return Ok(());
     }
-
-    #[test]
-    fn example3() {
-        let data = r"M.S
-.A.
-M.S
-";
-        let mut res = crawl(data.as_bytes(), true).unwrap();
-        res.sort();
-        assert_eq!(res, vec![Match{row: 1, col: 1, orient: Orient::XMas(false, false)}]);
+    if words.is_empty() {
+        words.push("XMAS".to_string());
     }
-    #[test]
-    fn example4() {
-        let data = r"MMMSXXMASM
-MSAMXMSMSA
-AMXSXMAAMM
-MSAMASMSMX
-XMASAMXAMM
-XXAMMXXAMA
-SMSMSASXSS
-SAXAMASAAA
-MAMMMXMMMM
-MXMXAXMASX
-";
-        let mut res = crawl(data.as_bytes(), true).unwrap();
-        res.sort();
-        println!("{:?}", res);
-        assert_eq!(res.len(), 9);
+    let words = words.iter().map(|w| w.as_bytes()).collect::<Vec<&[u8]>>();
+    let matches = day04::crawl_wrapped(&grid, &words, topology.unwrap_or(Topology::Plane))?;
+    let mut counts = vec![0; words.len()];
+    for m in &matches {
+        counts[m.word] += 1;
     }
+    for (word, count) in words.iter().zip(counts) {
+        println!("{}: {}", String::from_utf8_lossy(word), count);
+    }
+    println!("total: {}", matches.len());
+    Ok(())
 }
@@ -0,0 +1,195 @@
+use anyhow::anyhow;
+use common::Solution;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::io::BufRead;
+
+type Orderings = HashMap<usize, HashSet<usize>>;
+
+fn build_ordering<B: BufRead>(input: &mut std::io::Lines<B>) -> anyhow::Result<Orderings> {
+    let mut res: Orderings = HashMap::new();
+    for line in input {
+        let line = line?;
+        if line.is_empty() {
+            return Ok(res);
+        }
+        let (left, right) = line
+            .split_once('|')
+            .ok_or_else(|| anyhow!("bad input format"))?;
+        let (left, right) = (left.parse()?, right.parse()?);
+
+        res.entry(left)
+            .or_default()
+            .insert(right);
+    }
+    Err(anyhow!("unexpected EOF"))
+}
+
+fn check_job(line: &str, order: &Orderings) -> anyhow::Result<(bool, usize)> {
+    let mut seen: HashSet<usize> = Default::default();
+    let line = line
+        .split(',')
+        .map(|s| s.parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()?;
+    let 0 = (line.len() - 1).rem_euclid(2) else {
+        panic!("non-odd page count: {}", line.len());
+    };
+    let mid = (line.len() - 1) / 2;
+    let mid = line[mid];
+    for page in line {
+        if let Some(banned_precedents) = order.get(&page)
+            && !banned_precedents.is_disjoint(&seen)
+        {
+            return Ok((false, mid));
+        }
+        seen.insert(page);
+    }
+    Ok((true, mid))
+}
+
+fn fix(line: &str, orderings: &Orderings) -> anyhow::Result<usize> {
+    let mut line = line
+        .split(',')
+        .map(|s| s.parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    line.sort_by(|a, b| {
+        if let Some(m) = orderings.get(a)
+            && m.contains(b)
+        {
+            return core::cmp::Ordering::Less;
+        }
+        if let Some(m) = orderings.get(b)
+            && m.contains(a)
+        {
+            return core::cmp::Ordering::Greater;
+        }
+        a.cmp(b)
+    });
+
+    let mid = (line.len() - 1) / 2;
+    let mid = line[mid];
+    Ok(mid)
+}
+
+pub struct Manifest {
+    orderings: Orderings,
+    jobs: Vec<String>,
+}
+
+pub struct Day05;
+
+impl Solution for Day05 {
+    const DAY: u32 = 5;
+
+    type Input = Manifest;
+    type Answer = usize;
+
+    fn parse(input: &str) -> anyhow::Result<Manifest> {
+        let mut lines = input.as_bytes().lines();
+        let orderings = build_ordering(&mut lines)?;
+        let jobs = lines
+            .filter(|line| !matches!(line, Ok(line) if line.is_empty()))
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(Manifest { orderings, jobs })
+    }
+
+    fn part1(input: &Manifest) -> anyhow::Result<usize> {
+        let mut sum = 0;
+        for line in input.jobs.iter() {
+            let (ok, midpage) = check_job(line, &input.orderings)?;
+            if ok {
+                sum += midpage;
+            }
+        }
+        Ok(sum)
+    }
+
+    fn part2(input: &Manifest) -> anyhow::Result<usize> {
+        let mut sum = 0;
+        for line in input.jobs.iter() {
+            let (ok, _) = check_job(line, &input.orderings)?;
+            if !ok {
+                sum += fix(line, &input.orderings)?;
+            }
+        }
+        Ok(sum)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EXAMPLE1_DATA: &str = r"47|53
+97|13
+97|61
+97|47
+75|29
+61|13
+75|53
+29|13
+97|29
+53|29
+61|53
+97|53
+61|29
+47|13
+75|47
+97|75
+47|61
+75|61
+47|29
+75|13
+53|13
+
+75,47,61,53,29
+97,61,53,29,13
+75,29,13
+75,97,47,61,53
+61,13,29
+97,13,75,29,47
+";
+
+    #[test]
+    fn example1() {
+        let mut lines = BufRead::lines(EXAMPLE1_DATA.as_bytes());
+        let ordering = build_ordering(&mut lines).unwrap();
+        println!("ordering: {:?}", ordering);
+        assert_eq!(ordering.len(), 6);
+        assert_eq!(ordering[&97].len(), 6);
+        assert_eq!(ordering[&29].len(), 1);
+    }
+
+    #[test]
+    fn example2() {
+        let mut lines = BufRead::lines(EXAMPLE1_DATA.as_bytes());
+        let ordering = build_ordering(&mut lines).unwrap();
+
+        assert_eq!(check_job("75,47,61,53,29", &ordering).unwrap(), (true, 61));
+        assert_eq!(check_job("97,61,53,29,13", &ordering).unwrap(), (true, 53));
+        assert_eq!(check_job("75,29,13", &ordering).unwrap(), (true, 29));
+        assert_eq!(check_job("75,97,47,61,53", &ordering).unwrap(), (false, 47));
+        assert_eq!(check_job("61,13,29", &ordering).unwrap(), (false, 13));
+        assert_eq!(check_job("97,13,75,29,47", &ordering).unwrap(), (false, 75));
+    }
+
+    #[test]
+    fn example3() {
+        let mut lines = BufRead::lines(EXAMPLE1_DATA.as_bytes());
+        let ordering = build_ordering(&mut lines).unwrap();
+
+        assert_eq!(fix("75,97,47,61,53", &ordering).unwrap(), 47);
+        assert_eq!(fix("61,13,29", &ordering).unwrap(), 29);
+        assert_eq!(fix("97,13,75,29,47", &ordering).unwrap(), 47);
+        assert_eq!(fix("92,99,88,55,15,78,77,68,72,18,91,67,64,82,61,63,44,71,38,97,43", &ordering).unwrap(), 68);
+    }
+
+    #[test]
+    fn example_parts() {
+        let manifest = Day05::parse(EXAMPLE1_DATA).unwrap();
+        assert_eq!(manifest.jobs.len(), 6);
+        assert_eq!(Day05::part1(&manifest).unwrap(), 143);
+        assert_eq!(Day05::part2(&manifest).unwrap(), 123);
+    }
+}
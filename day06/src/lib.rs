@@ -0,0 +1,228 @@
+use anyhow::anyhow;
+use anyhow::Result;
+use common::Solution;
+use grid::{Grid, Pos};
+
+#[derive(Clone)]
+pub struct Map {
+    obstacles: Grid<bool>,
+    start: Pos,
+}
+
+impl Map {
+    /// # Panics
+    /// Panics if passed an out-of-bounds position.
+    fn get(&self, pos: Pos) -> bool {
+        self.obstacles[pos]
+    }
+
+    /// # Panics
+    /// Panics if an already obstructed or out-of-range square is passed.
+    fn clone_obstruct(&self, pos: Pos) -> Self {
+        let mut dup = self.clone();
+        let b = &mut dup.obstacles[pos];
+        if *b {
+            panic!("tried to obstruct a blocked square, ({}, {})", pos.0, pos.1);
+        }
+        *b = true;
+        dup
+    }
+}
+
+#[derive(Eq, Hash, PartialEq, Clone, Copy)]
+enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction {
+    fn add_diff(&self, pos: Pos, map: &Map) -> Result<Pos, ()> {
+        let diff = match *self {
+            Direction::Up => grid::UP,
+            Direction::Down => grid::DOWN,
+            Direction::Right => grid::RIGHT,
+            Direction::Left => grid::LEFT,
+        };
+        map.obstacles.offset(pos, diff).ok_or(())
+    }
+
+    fn as_bitmask(self) -> u8 {
+        match self {
+            Direction::Up => 1 << 0,
+            Direction::Right => 1 << 1,
+            Direction::Down => 1 << 2,
+            Direction::Left => 1 << 3,
+        }
+    }
+
+    fn right_turn(self) -> Self {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+}
+
+fn build_map(input: &str) -> Result<Map> {
+    let mut start = None;
+    let obstacles = Grid::parse(input, |c, pos| match c {
+        b'.' => Ok(false),
+        b'^' => {
+            if start.replace(pos).is_some() {
+                return Err(anyhow!("multiple guard starts at {:?}", pos));
+            }
+            Ok(false)
+        }
+        b'#' => Ok(true),
+        _ => Err(anyhow!("invalid character {}", c)),
+    })?;
+    let Some(start) = start else {
+        Err(anyhow!("no start token found"))?
+    };
+    Ok(Map { obstacles, start })
+}
+
+fn traverse_map(map: &Map) -> Result<Grid<u8>, ()> {
+    let mut visited_dir = map.obstacles.map(|_| 0u8);
+
+    let mut pos: Pos = map.start;
+    let mut dir = Direction::Up;
+
+    let mut insert = |pos: Pos, dir: Direction| -> bool {
+        let e = &mut visited_dir[pos];
+        if *e & dir.as_bitmask() == 0 {
+            *e |= dir.as_bitmask();
+            true
+        } else {
+            false
+        }
+    };
+    insert(pos, dir);
+
+    while let Ok(next) = dir.add_diff(pos, map) {
+        match map.get(next) {
+            false => {
+                if !insert(next, dir) {
+                    // Detected loop
+                    return Err(());
+                }
+                pos = next;
+            }
+            true => {
+                dir = dir.right_turn();
+            }
+        }
+    }
+
+    Ok(visited_dir)
+}
+
+fn count_visited(data: &Grid<u8>) -> usize {
+    data.iter().filter(|(_, c)| **c != 0).count()
+}
+
+fn count_loops(map: &Map, traversal_data: &Grid<u8>) -> usize {
+    let mut count = 0;
+
+    let mut progress = 0;
+    let total = count_visited(traversal_data);
+    for (candidate, cell) in traversal_data.iter() {
+        if *cell != 0 {
+            progress += 1;
+            if progress % 100 == 0 {
+                common::debug!("checking candidate {}/{}", progress, total);
+            }
+            if candidate == map.start {
+                continue;
+            }
+            let new_map = map.clone_obstruct(candidate);
+            if let Err(()) = traverse_map(&new_map) {
+                count += 1;
+            }
+        }
+    }
+
+    count
+}
+
+pub struct Day06;
+
+impl Solution for Day06 {
+    const DAY: u32 = 6;
+
+    type Input = Map;
+    type Answer = usize;
+
+    fn parse(input: &str) -> Result<Map> {
+        build_map(input)
+    }
+
+    fn part1(map: &Map) -> Result<usize> {
+        let steps = traverse_map(map).map_err(|()| anyhow!("input map has a loop"))?;
+        Ok(count_visited(&steps))
+    }
+
+    fn part2(map: &Map) -> Result<usize> {
+        let steps = traverse_map(map).map_err(|()| anyhow!("input map has a loop"))?;
+        Ok(count_loops(map, &steps))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EXAMPLE1_DATA: &str = r"....#.....
+.........#
+..........
+..#.......
+.......#..
+..........
+.#..^.....
+........#.
+#.........
+......#...
+";
+
+    #[test]
+    fn example() {
+        let map = build_map(EXAMPLE1_DATA).unwrap();
+
+        assert_eq!(map.obstacles.rows(), 10);
+        assert_eq!(map.obstacles.cols(), 10);
+        assert_eq!(map.start, (6, 4));
+
+        let steps = traverse_map(&map).unwrap();
+        assert_eq!(count_visited(&steps), 41);
+
+        let possible = count_loops(&map, &steps);
+        assert_eq!(possible, 6);
+    }
+
+    #[test]
+    fn test_add_diff() {
+        let map = build_map(EXAMPLE1_DATA).unwrap();
+
+        assert_eq!(Direction::Up.add_diff((1, 5), &map), Ok((0, 5)));
+        assert_eq!(Direction::Down.add_diff((1, 5), &map), Ok((2, 5)));
+        assert_eq!(Direction::Right.add_diff((1, 5), &map), Ok((1, 6)));
+        assert_eq!(Direction::Left.add_diff((1, 5), &map), Ok((1, 4)));
+
+        assert_eq!(Direction::Up.add_diff((0, 5), &map), Err(()));
+        assert_eq!(Direction::Down.add_diff((9, 5), &map), Err(()));
+        assert_eq!(Direction::Right.add_diff((1, 9), &map), Err(()));
+        assert_eq!(Direction::Left.add_diff((1, 0), &map), Err(()));
+    }
+
+    #[test]
+    fn bad_maps() {
+        assert!(build_map("..#\n...\n").is_err());
+        assert!(build_map("..^\n..\n").is_err());
+        assert!(build_map("..^\n.x.\n").is_err());
+        assert!(build_map("..^\n.^.\n").is_err());
+    }
+}
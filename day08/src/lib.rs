@@ -0,0 +1,215 @@
+use anyhow::{anyhow, Result};
+use common::Solution;
+use grid::{Grid, Pos};
+use std::collections::HashSet;
+
+#[derive(Clone)]
+pub struct Map {
+    /// Antenna frequency, or 0 for an empty square.
+    antennas: Grid<u8>,
+}
+
+fn build_map(input: &str) -> Result<Map> {
+    let antennas = Grid::parse(input, |c, _| match c {
+        b'.' => Ok(0),
+        b'#' => Ok(0),
+        c if c.is_ascii_alphanumeric() => Ok(c),
+        _ => Err(anyhow!("invalid map char {}", c)),
+    })?;
+    Ok(Map { antennas })
+}
+
+fn antenna_ids(map: &Map) -> HashSet<u8> {
+    map.antennas
+        .iter()
+        .map(|(_, c)| *c)
+        .filter(|c| *c != 0)
+        .collect()
+}
+
+/// The antinode on the far side of `a` from `b`.
+fn single_antinodes(map: &Map, a: Pos, b: Pos) -> Option<Pos> {
+    let diff = (
+        a.0 as isize - b.0 as isize,
+        a.1 as isize - b.1 as isize,
+    );
+    map.antennas.offset(a, diff)
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+fn antinodes(map: &Map) -> Grid<bool> {
+    find_antinodes(map, false)
+}
+
+/// Part 2 antinodes: every position in line with two same-frequency antennas,
+/// including the antennas themselves.
+fn resonant_antinodes(map: &Map) -> Grid<bool> {
+    find_antinodes(map, true)
+}
+
+fn find_antinodes(map: &Map, resonant: bool) -> Grid<bool> {
+    let mut antinodes_data = map.antennas.map(|_| false);
+
+    let mut set = |pos: Pos| {
+        antinodes_data[pos] = true;
+    };
+
+    for antenna_id in antenna_ids(map) {
+        let matches = map.antennas.find_all(&antenna_id).collect::<Vec<Pos>>();
+        use itertools::Itertools;
+
+        for cvec in matches.iter().combinations(2) {
+            let [&lpos, &rpos] = cvec.try_into().unwrap();
+            if resonant {
+                // The smallest step between grid cells on the line.
+                let (dr, dc) = (
+                    lpos.0 as isize - rpos.0 as isize,
+                    lpos.1 as isize - rpos.1 as isize,
+                );
+                let div = gcd(dr.unsigned_abs(), dc.unsigned_abs()) as isize;
+                let (dr, dc) = (dr / div, dc / div);
+                set(lpos);
+                for step in [(dr, dc), (-dr, -dc)] {
+                    let mut cur = lpos;
+                    while let Some(next) = map.antennas.offset(cur, step) {
+                        set(next);
+                        cur = next;
+                    }
+                }
+                continue;
+            }
+            let na = single_antinodes(map, lpos, rpos);
+            if let Some(na) = na {
+                set(na);
+            }
+            let nb = single_antinodes(map, rpos, lpos);
+            if let Some(nb) = nb {
+                set(nb);
+            }
+        }
+    }
+    antinodes_data
+}
+
+fn count_antinodes(antinodes: &Grid<bool>) -> usize {
+    antinodes.find_all(&true).count()
+}
+
+pub struct Day08;
+
+impl Solution for Day08 {
+    const DAY: u32 = 8;
+
+    type Input = Map;
+    type Answer = usize;
+
+    fn parse(input: &str) -> Result<Map> {
+        build_map(input)
+    }
+
+    fn part1(map: &Map) -> Result<usize> {
+        Ok(count_antinodes(&antinodes(map)))
+    }
+
+    fn part2(map: &Map) -> Result<usize> {
+        Ok(count_antinodes(&resonant_antinodes(map)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EXAMPLE1_DATA: &str = r"............
+........0...
+.....0......
+.......0....
+....0.......
+......A.....
+............
+............
+........A...
+.........A..
+............
+............";
+    const EXAMPLE1_ANTINODES: &str = r"......#....#
+...#....0...
+....#0....#.
+..#....0....
+....0....#..
+.#....#.....
+...#........
+#......#....
+........A...
+.........A..
+..........#.
+..........#.";
+
+    const EXAMPLE2_DATA: &str = r"..........
+...#......
+#.........
+....a.....
+........a.
+.....a....
+..#.......
+......A...
+..........
+..........";
+    #[test]
+    fn example1() {
+        let map = build_map(EXAMPLE1_DATA).unwrap();
+        assert_eq!(antenna_ids(&map), HashSet::from([b'0', b'A']));
+
+        let expected_antinodes = Grid::parse(EXAMPLE1_ANTINODES, |c, _| Ok(c == b'#')).unwrap();
+        let antinodes = antinodes(&map);
+        assert_eq!(antinodes, expected_antinodes);
+        assert_eq!(count_antinodes(&antinodes), 14);
+    }
+
+    #[test]
+    fn example2() {
+        let map = build_map(EXAMPLE2_DATA).unwrap();
+        assert_eq!(antenna_ids(&map), HashSet::from([b'a', b'A']));
+
+        let expected_antinodes = {
+            let mut expected_antinodes = Grid::parse(EXAMPLE2_DATA, |c, _| Ok(c == b'#')).unwrap();
+            expected_antinodes[(7, 6)] = true;
+            expected_antinodes
+        };
+        let antinodes = antinodes(&map);
+        assert_eq!(antinodes, expected_antinodes);
+    }
+
+    #[test]
+    fn example1_resonant() {
+        let map = build_map(EXAMPLE1_DATA).unwrap();
+        assert_eq!(count_antinodes(&resonant_antinodes(&map)), 34);
+    }
+
+    #[test]
+    fn example3_resonant() {
+        let data = r"T.........
+...T......
+.T........
+..........
+..........
+..........
+..........
+..........
+..........
+..........";
+        let map = Day08::parse(data).unwrap();
+        assert_eq!(Day08::part2(&map).unwrap(), 9);
+    }
+
+    #[test]
+    fn resonant_steps_between_antennas() {
+        // The antennas are two cells apart, with grid cells in line between.
+        let data = "a....\n.....\n..a..\n.....\n....a\n";
+        let map = Day08::parse(data).unwrap();
+        assert_eq!(Day08::part2(&map).unwrap(), 5);
+    }
+}
@@ -0,0 +1,379 @@
+//! Rectangular 2D grids parsed from puzzle character maps.
+//!
+//! Positions are `(row, col)` with `(0, 0)` at the top left, and offsets are
+//! signed `(drow, dcol)` pairs.
+
+use anyhow::{anyhow, Result};
+use std::ops::{Index, IndexMut};
+
+pub type Pos = (usize, usize);
+pub type Offset = (isize, isize);
+
+pub const UP: Offset = (-1, 0);
+pub const RIGHT: Offset = (0, 1);
+pub const DOWN: Offset = (1, 0);
+pub const LEFT: Offset = (0, -1);
+
+/// Orthogonal neighbours, clockwise from up.
+pub const DIRS4: [Offset; 4] = [UP, RIGHT, DOWN, LEFT];
+/// Orthogonal and diagonal neighbours, clockwise from up.
+pub const DIRS8: [Offset; 8] = [
+    UP,
+    (-1, 1),
+    RIGHT,
+    (1, 1),
+    DOWN,
+    (1, -1),
+    LEFT,
+    (-1, -1),
+];
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Grid<T> {
+    /// Row-major, `rows * cols` long.
+    cells: Vec<T>,
+    rows: usize,
+    cols: usize,
+}
+
+impl<T> Grid<T> {
+    pub fn new(rows: usize, cols: usize, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        Grid {
+            cells: vec![fill; rows * cols],
+            rows,
+            cols,
+        }
+    }
+
+    /// Builds a grid from rows that must all be the same length.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Self> {
+        let cols = rows.first().map(|r| r.len()).unwrap_or(0);
+        let nrows = rows.len();
+        let mut cells = Vec::with_capacity(nrows * cols);
+        for (i, row) in rows.into_iter().enumerate() {
+            if row.len() != cols {
+                return Err(anyhow!(
+                    "inconsistent line length on line {}: expected {} got {}",
+                    i + 1,
+                    cols,
+                    row.len()
+                ));
+            }
+            cells.extend(row);
+        }
+        Ok(Grid {
+            cells,
+            rows: nrows,
+            cols,
+        })
+    }
+
+    /// Parses a character map, one row per line, mapping each byte with `f`.
+    ///
+    /// Parsing stops at the first empty line, so a trailing newline (or a
+    /// following blank-separated section) is not part of the grid.
+    pub fn parse(input: &str, mut f: impl FnMut(u8, Pos) -> Result<T>) -> Result<Self> {
+        let rows = input
+            .lines()
+            .take_while(|line| !line.is_empty())
+            .enumerate()
+            .map(|(row, line)| {
+                line.bytes()
+                    .enumerate()
+                    .map(|(col, c)| {
+                        f(c, (row, col)).map_err(|e| e.context(format!("at row {} col {}", row, col)))
+                    })
+                    .collect::<Result<Vec<T>>>()
+            })
+            .collect::<Result<Vec<Vec<T>>>>()?;
+        Self::from_rows(rows)
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn in_bounds(&self, pos: Pos) -> bool {
+        pos.0 < self.rows && pos.1 < self.cols
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        if self.in_bounds(pos) {
+            self.cells.get(pos.0 * self.cols + pos.1)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        if self.in_bounds(pos) {
+            self.cells.get_mut(pos.0 * self.cols + pos.1)
+        } else {
+            None
+        }
+    }
+
+    /// Moves `pos` by `diff`, or `None` if that leaves the grid.
+    pub fn offset(&self, pos: Pos, diff: Offset) -> Option<Pos> {
+        let moved = (
+            pos.0.checked_add_signed(diff.0)?,
+            pos.1.checked_add_signed(diff.1)?,
+        );
+        self.in_bounds(moved).then_some(moved)
+    }
+
+    /// In-bounds orthogonal neighbours of `pos`.
+    pub fn neighbors4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        DIRS4.into_iter().filter_map(move |d| self.offset(pos, d))
+    }
+
+    /// In-bounds orthogonal and diagonal neighbours of `pos`.
+    pub fn neighbors8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        DIRS8.into_iter().filter_map(move |d| self.offset(pos, d))
+    }
+
+    /// Positions from `start` (inclusive) stepping by `step` until leaving the grid.
+    pub fn ray(&self, start: Pos, step: Offset) -> impl Iterator<Item = Pos> + '_ {
+        std::iter::successors(self.in_bounds(start).then_some(start), move |p| {
+            self.offset(*p, step)
+        })
+    }
+
+    /// # Panics
+    /// Panics if `row` is out of range.
+    pub fn row(&self, row: usize) -> &[T] {
+        assert!(row < self.rows, "row {} out of range", row);
+        &self.cells[row * self.cols..(row + 1) * self.cols]
+    }
+
+    /// # Panics
+    /// Panics if `col` is out of range.
+    pub fn col(&self, col: usize) -> impl Iterator<Item = &T> + '_ {
+        assert!(col < self.cols, "col {} out of range", col);
+        self.cells.iter().skip(col).step_by(self.cols)
+    }
+
+    /// Every diagonal running down-right, each from its top-left end.
+    pub fn diagonals(&self) -> impl Iterator<Item = impl Iterator<Item = Pos> + '_> + '_ {
+        let starts = (0..self.rows)
+            .rev()
+            .map(|r| (r, 0))
+            .chain((1..self.cols).map(|c| (0, c)));
+        starts.map(move |s| self.ray(s, (1, 1)))
+    }
+
+    /// Every diagonal running down-left, each from its top-right end.
+    pub fn anti_diagonals(&self) -> impl Iterator<Item = impl Iterator<Item = Pos> + '_> + '_ {
+        let last = self.cols.saturating_sub(1);
+        let starts = (0..self.cols)
+            .map(|c| (0, c))
+            .chain((1..self.rows).map(move |r| (r, last)));
+        starts.map(move |s| self.ray(s, (1, -1)))
+    }
+
+    pub fn rows_iter(&self) -> impl Iterator<Item = &[T]> + '_ {
+        self.cells.chunks(self.cols.max(1)).take(self.rows)
+    }
+
+    /// All positions in row-major order.
+    pub fn positions(&self) -> impl Iterator<Item = Pos> + use<T> {
+        let cols = self.cols;
+        (0..self.rows).flat_map(move |r| (0..cols).map(move |c| (r, c)))
+    }
+
+    /// All cells with their positions in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> + '_ {
+        self.positions().zip(self.cells.iter())
+    }
+
+    /// First position (row-major) holding `value`.
+    pub fn find(&self, value: &T) -> Option<Pos>
+    where
+        T: PartialEq,
+    {
+        self.find_all(value).next()
+    }
+
+    pub fn find_all<'a>(&'a self, value: &'a T) -> impl Iterator<Item = Pos> + 'a
+    where
+        T: PartialEq,
+    {
+        self.iter().filter(move |(_, v)| *v == value).map(|(p, _)| p)
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            cells: self.cells.iter().map(f).collect(),
+            rows: self.rows,
+            cols: self.cols,
+        }
+    }
+
+    /// The grid turned a quarter turn clockwise.
+    pub fn rotated(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        // Each new row is an old column, read from the bottom up.
+        let cells = (0..self.cols)
+            .flat_map(|c| (0..self.rows).rev().map(move |r| (r, c)))
+            .map(|pos| self[pos].clone())
+            .collect();
+        Grid {
+            cells,
+            rows: self.cols,
+            cols: self.rows,
+        }
+    }
+
+    /// The grid flipped left to right.
+    pub fn mirrored(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        let cells = self
+            .rows_iter()
+            .flat_map(|row| row.iter().rev().cloned())
+            .collect();
+        Grid {
+            cells,
+            rows: self.rows,
+            cols: self.cols,
+        }
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &T {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("position {:?} out of range", pos))
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        self.get_mut(pos)
+            .unwrap_or_else(|| panic!("position {:?} out of range", pos))
+    }
+}
+
+impl Grid<u8> {
+    /// Parses a character map keeping the raw bytes.
+    pub fn parse_bytes(input: &str) -> Result<Self> {
+        Self::parse(input, |c, _| Ok(c))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const DATA: &str = "abc\ndef\n";
+
+    #[test]
+    fn parse() {
+        let g = Grid::parse_bytes(DATA).unwrap();
+        assert_eq!((g.rows(), g.cols()), (2, 3));
+        assert_eq!(g[(1, 2)], b'f');
+        assert_eq!(g.get((2, 0)), None);
+        assert_eq!(g.get((0, 3)), None);
+
+        let no_trailing = Grid::parse_bytes("abc\ndef").unwrap();
+        assert_eq!(no_trailing, g);
+
+        let stops = Grid::parse_bytes("abc\ndef\n\nrest").unwrap();
+        assert_eq!(stops, g);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let err = Grid::parse_bytes("abc\nde\n").unwrap_err();
+        assert!(err.to_string().contains("line 2"), "{}", err);
+
+        let err = Grid::parse(DATA, |c, _| match c {
+            b'a'..=b'd' => Ok(true),
+            _ => Err(anyhow!("invalid character {}", c)),
+        })
+        .unwrap_err();
+        assert_eq!(err.to_string(), "at row 1 col 1");
+    }
+
+    #[test]
+    fn offsets() {
+        let g = Grid::parse_bytes(DATA).unwrap();
+        assert_eq!(g.offset((0, 0), DOWN), Some((1, 0)));
+        assert_eq!(g.offset((0, 0), UP), None);
+        assert_eq!(g.offset((0, 0), LEFT), None);
+        assert_eq!(g.offset((1, 2), RIGHT), None);
+        assert_eq!(g.offset((1, 2), DOWN), None);
+        assert_eq!(g.offset((1, 2), (-1, -2)), Some((0, 0)));
+    }
+
+    #[test]
+    fn neighbors() {
+        let g = Grid::new(3, 3, 0u8);
+        assert_eq!(g.neighbors4((1, 1)).count(), 4);
+        assert_eq!(g.neighbors8((1, 1)).count(), 8);
+        assert_eq!(
+            g.neighbors4((0, 0)).collect::<Vec<_>>(),
+            vec![(0, 1), (1, 0)]
+        );
+        assert_eq!(
+            g.neighbors8((0, 2)).collect::<Vec<_>>(),
+            vec![(1, 2), (1, 1), (0, 1)]
+        );
+    }
+
+    #[test]
+    fn lines() {
+        let g = Grid::parse_bytes(DATA).unwrap();
+        assert_eq!(g.row(1), b"def");
+        assert_eq!(g.col(1).copied().collect::<Vec<_>>(), b"be");
+        assert_eq!(g.rows_iter().collect::<Vec<_>>(), vec![b"abc", b"def"]);
+
+        let text = |it: &mut dyn Iterator<Item = Pos>| {
+            String::from_utf8(it.map(|p| g[p]).collect()).unwrap()
+        };
+        let diags = g
+            .diagonals()
+            .map(|mut d| text(&mut d))
+            .collect::<Vec<_>>();
+        assert_eq!(diags, vec!["d", "ae", "bf", "c"]);
+        let anti = g
+            .anti_diagonals()
+            .map(|mut d| text(&mut d))
+            .collect::<Vec<_>>();
+        assert_eq!(anti, vec!["a", "bd", "ce", "f"]);
+        assert_eq!(text(&mut g.ray((0, 2), LEFT)), "cba");
+        assert_eq!(g.ray((5, 5), LEFT).count(), 0);
+    }
+
+    #[test]
+    fn transforms() {
+        let g = Grid::parse_bytes(DATA).unwrap();
+        assert_eq!(g.rotated(), Grid::parse_bytes("da\neb\nfc").unwrap());
+        assert_eq!(g.mirrored(), Grid::parse_bytes("cba\nfed").unwrap());
+        assert_eq!(g.rotated().rotated().rotated().rotated(), g);
+        let empty = Grid::new(0, 0, 0u8);
+        assert_eq!(empty.rotated(), empty);
+    }
+
+    #[test]
+    fn find() {
+        let mut g = Grid::parse(".#.\n..#\n", |c, _| Ok(c == b'#')).unwrap();
+        assert_eq!(g.find(&true), Some((0, 1)));
+        assert_eq!(g.find_all(&true).collect::<Vec<_>>(), vec![(0, 1), (1, 2)]);
+        g[(0, 1)] = false;
+        assert_eq!(g.find(&true), Some((1, 2)));
+        assert_eq!(g.map(|b| *b as u8).iter().map(|(_, v)| *v as usize).sum::<usize>(), 1);
+    }
+}
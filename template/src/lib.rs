@@ -0,0 +1,37 @@
+use common::Solution;
+
+pub struct DayXX;
+
+impl Solution for DayXX {
+    const DAY: u32 = DAY_NUMBER;
+
+    type Input = Vec<String>;
+    type Answer = usize;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        Ok(input.lines().map(String::from).collect())
+    }
+
+    fn part1(input: &Self::Input) -> anyhow::Result<usize> {
+        Ok(input.len())
+    }
+
+    /// Placeholder, so `aoc verify` reports the part as missing until the
+    /// answer is filled in.
+    fn part2(_input: &Self::Input) -> anyhow::Result<usize> {
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EXAMPLE1_DATA: &str = include_str!("../examples/example1");
+
+    #[test]
+    fn example1() {
+        let input = DayXX::parse(EXAMPLE1_DATA).unwrap();
+        assert_eq!(DayXX::part1(&input).unwrap(), 0);
+    }
+}
@@ -1,19 +1,3 @@
-use std::io::BufRead;
-
-fn main() -> Result<(), anyhow::Error> {
-    let input = std::io::stdin().lock().lines();
-
-    for line in input {
-        let line = line?;
-    }
-    Ok(())
-}
-
-#[cfg(test)]
-mod test {
-    use super::*;
-
-    #[test]
-    fn example() {
-    }
+fn main() -> anyhow::Result<()> {
+    common::cli::main::<dayXX::DayXX>()
 }